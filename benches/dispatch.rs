@@ -0,0 +1,49 @@
+use chip_8::chip8::Chip8;
+use chip_8::rom::Rom;
+use criterion::{criterion_group, criterion_main, Criterion};
+
+const CYCLES: u32 = 100_000;
+
+type Setup = fn(&mut Chip8);
+
+fn machine(rom: &[u8], setup: Setup) -> Chip8 {
+    let mut chip8 = Chip8::new();
+    chip8.seed(0);
+    setup(&mut chip8);
+    chip8.init();
+    chip8
+        .load_rom(Rom::from_bytes(rom.to_vec()).unwrap())
+        .unwrap();
+    chip8
+}
+
+fn bench_rom(c: &mut Criterion, name: &str, rom: &[u8]) {
+    #[allow(unused_mut)]
+    let mut engines: Vec<(&str, Setup)> = vec![
+        ("decode", |chip8| chip8.set_decode_cache(false)),
+        ("cached", |chip8| chip8.set_decode_cache(true)),
+    ];
+    #[cfg(feature = "dynarec")]
+    engines.push(("dynarec", |chip8| chip8.set_dynarec(true)));
+
+    let mut group = c.benchmark_group(name);
+    for &(label, setup) in &engines {
+        group.bench_function(label, |b| {
+            b.iter_batched_ref(
+                || machine(rom, setup),
+                |chip8| chip8.run_cycles(CYCLES),
+                criterion::BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn dispatch(c: &mut Criterion) {
+    // BRIX spends most of its time in a delay loop, TETRIS in drawing
+    bench_rom(c, "BRIX", include_bytes!("../roms/BRIX"));
+    bench_rom(c, "TETRIS", include_bytes!("../roms/TETRIS"));
+}
+
+criterion_group!(benches, dispatch);
+criterion_main!(benches);
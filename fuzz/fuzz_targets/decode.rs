@@ -0,0 +1,7 @@
+#![no_main]
+use chip_8::opcode;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|raw: u16| {
+    let _ = opcode::decode(raw).to_string();
+});
@@ -0,0 +1,37 @@
+#![no_main]
+use chip_8::chip8::Chip8;
+use chip_8::quirks::{QuirkPreset, Quirks};
+use chip_8::rom::Rom;
+use libfuzzer_sys::fuzz_target;
+
+const FRAMES: u32 = 1000;
+const CYCLES_PER_FRAME: u32 = 10;
+const PRESETS: [QuirkPreset; 3] = [QuirkPreset::Chip8, QuirkPreset::Vip, QuirkPreset::Schip];
+
+// the first byte picks the quirk preset and a key held down throughout,
+// the rest is the program
+fuzz_target!(|data: &[u8]| {
+    let (settings, program) = match data.split_first() {
+        Some(split) => split,
+        None => return,
+    };
+    let rom = match Rom::from_bytes(program.to_vec()) {
+        Ok(rom) => rom,
+        Err(_) => return,
+    };
+    let mut chip8 = Chip8::new();
+    chip8.set_quirks(Quirks::from_preset(PRESETS[*settings as usize % 3]));
+    chip8.seed(0);
+    chip8.init();
+    if chip8.load_rom(rom).is_err() {
+        return;
+    }
+    chip8.set_key(settings >> 4);
+    for _ in 0..FRAMES {
+        chip8.run_cycles(CYCLES_PER_FRAME);
+        chip8.tick_timers();
+        if chip8.fault().is_some() {
+            break;
+        }
+    }
+});
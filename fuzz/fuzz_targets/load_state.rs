@@ -0,0 +1,20 @@
+#![no_main]
+use chip_8::chip8::Chip8;
+use libfuzzer_sys::fuzz_target;
+
+const CYCLES: u32 = 1000;
+
+fuzz_target!(|data: &[u8]| {
+    let mut chip8 = Chip8::new();
+    chip8.seed(0);
+    if chip8.load_state(data).is_err() {
+        return;
+    }
+    // whatever was loaded has to keep running and survive another round trip
+    chip8.run_cycles(CYCLES);
+    let saved = chip8.save_state();
+    let mut copy = Chip8::new();
+    copy.load_state(&saved)
+        .expect("a saved state could not be loaded");
+    assert_eq!(copy.save_state(), saved);
+});
@@ -0,0 +1,71 @@
+// Fills corpus/ with inputs derived from the ROMs, run from the fuzz directory:
+//
+//     cargo run --bin seed_corpus [ROM_DIR]
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+use chip_8::chip8::Chip8;
+use chip_8::rom::Rom;
+
+const STATE_CYCLES: [u32; 3] = [0, 100, 1000]; // save states taken after this many
+
+fn main() {
+    let rom_dir = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "../roms".to_string());
+    for target in &["decode", "execute", "load_state"] {
+        fs::create_dir_all(Path::new("corpus").join(target)).expect("Could not create corpus");
+    }
+
+    let mut opcodes = BTreeSet::new();
+    for entry in fs::read_dir(&rom_dir).expect("Could not open ROM directory") {
+        let path = entry.expect("Could not read ROM directory").path();
+        let name = path.file_name().unwrap().to_string_lossy().into_owned();
+        let rom = match Rom::load(&path) {
+            Ok(rom) => rom,
+            Err(e) => {
+                eprintln!("Skipping {:?}: {}", path, e);
+                continue;
+            }
+        };
+        let data = rom.data().to_vec();
+        opcodes.extend(data.chunks_exact(2).map(|pair| [pair[0], pair[1]]));
+
+        // one input per quirk preset, see fuzz_targets/execute.rs
+        for preset in 0..3u8 {
+            let mut input = vec![preset];
+            input.extend_from_slice(&data);
+            write(&format!("execute/{}-{}", name, preset), &input);
+        }
+
+        for cycles in &STATE_CYCLES {
+            let mut chip8 = Chip8::new();
+            chip8.seed(0);
+            chip8.init();
+            if chip8
+                .load_rom(Rom::from_bytes(data.clone()).unwrap())
+                .is_err()
+            {
+                break;
+            }
+            chip8.run_cycles(*cycles);
+            write(
+                &format!("load_state/{}-{}", name, cycles),
+                &chip8.save_state(),
+            );
+        }
+    }
+    for opcode in &opcodes {
+        write(
+            &format!("decode/{:02X}{:02X}", opcode[0], opcode[1]),
+            opcode,
+        );
+    }
+}
+
+fn write(name: &str, data: &[u8]) {
+    let path = Path::new("corpus").join(name);
+    fs::write(&path, data).unwrap_or_else(|e| panic!("Could not write {:?}: {}", path, e));
+}
@@ -0,0 +1,331 @@
+use std::collections::BTreeSet;
+use std::fmt;
+
+use super::opcode::{self, OpCode};
+use super::platform::Descriptor;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Extension {
+    SuperChip,
+    XoChip,
+}
+
+#[rustfmt::skip]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Finding {
+    JumpOutsideRom { at: u16, target: u16 },
+    UnknownOpcode { at: u16, raw: u16 },
+    RunsOffEnd { at: u16 },
+    UnresolvedJump { at: u16 },             // Bnnn, the target depends on a register
+    MachineCode { at: u16, addr: u16 },     // 0nnn calls into native routines
+    SelfModifying { at: u16, target: u16 }, // stores into reachable code
+    Extension { at: u16, raw: u16, extension: Extension },
+    TooLarge { size: usize, max: usize, platform: &'static str },
+}
+
+pub struct Report {
+    pub size: usize,
+    pub reachable: BTreeSet<u16>, // addresses of reachable instructions
+    pub findings: Vec<Finding>,
+    pub suggested_platform: Option<&'static Descriptor>, // None for XO-CHIP, which can't be run
+}
+
+impl Report {
+    pub fn uses(&self, extension: Extension) -> bool {
+        self.findings.iter().any(|finding| match finding {
+            Finding::Extension { extension: e, .. } => *e == extension,
+            _ => false,
+        })
+    }
+}
+
+// state carried along each path; only the index register is tracked so that
+// stores through a constant address can be checked against the code
+#[derive(Copy, Clone)]
+struct Path {
+    pc: u16,
+    i_reg: Option<u16>,
+}
+
+enum Flow {
+    Next,      // continue with the following instruction
+    Skip,      // continue with the following or the one after
+    Jump(u16), // continue at the target only
+    Call(u16), // continue at the target and after the call
+    Stop,      // no known successor
+}
+
+// Recursive-descent pass over the ROM, following every path from where the
+// platform loads it.
+pub fn analyze(rom: &[u8], platform: &'static Descriptor) -> Report {
+    let start = platform.load_address;
+    let end = start as usize + rom.len();
+    let fetch = |addr: u16| -> Option<u16> {
+        let index = (addr as usize).checked_sub(start as usize)?;
+        if index + 1 < rom.len() {
+            Some((rom[index] as u16) << 8 | rom[index + 1] as u16)
+        } else {
+            None
+        }
+    };
+
+    let mut reachable = BTreeSet::new();
+    let mut findings = Vec::new();
+    // (instruction, first address, last address) of stores through a known I
+    let mut writes: Vec<(u16, u16, u16)> = Vec::new();
+    let mut work = vec![Path {
+        pc: start,
+        i_reg: None,
+    }];
+
+    while let Some(mut path) = work.pop() {
+        if !reachable.insert(path.pc) {
+            continue;
+        }
+        let at = path.pc;
+        let raw = match fetch(at) {
+            Some(raw) => raw,
+            None => {
+                findings.push(Finding::RunsOffEnd { at });
+                continue;
+            }
+        };
+
+        let flow = if let Some(extension) = extension(raw) {
+            findings.push(Finding::Extension { at, raw, extension });
+            match (raw, at.checked_add(4)) {
+                (0x00FD, _) => Flow::Stop,                // exit
+                (0xF000, Some(next)) => Flow::Jump(next), // i := long nnnn, four bytes long
+                (0xF000, None) => {
+                    findings.push(Finding::RunsOffEnd { at });
+                    Flow::Stop
+                }
+                _ => Flow::Next,
+            }
+        } else {
+            match opcode::decode(raw) {
+                OpCode::Unknown(_) => {
+                    findings.push(Finding::UnknownOpcode { at, raw });
+                    Flow::Stop
+                }
+                opcode => step(opcode, at, &mut path, &mut findings, &mut writes),
+            }
+        };
+
+        // None past the end of the address space
+        let outside = |target: u16| target < start || target as usize >= end;
+        let mut follow = |target: Option<u16>, path: Path| match target {
+            Some(target) if outside(target) => {
+                findings.push(Finding::JumpOutsideRom { at, target })
+            }
+            Some(target) => work.push(Path { pc: target, ..path }),
+            None => findings.push(Finding::RunsOffEnd { at }),
+        };
+        let next = at.checked_add(2);
+        match flow {
+            Flow::Next => follow(next, path),
+            Flow::Skip => {
+                follow(next, path);
+                follow(at.checked_add(4), path);
+            }
+            Flow::Jump(target) => follow(Some(target), path),
+            Flow::Call(target) => {
+                follow(Some(target), path);
+                follow(next, path);
+            }
+            Flow::Stop => (),
+        }
+    }
+
+    for (at, first, last) in writes {
+        if let Some(target) = (first..=last)
+            .find(|addr| reachable.contains(addr) || (*addr > 0 && reachable.contains(&(addr - 1))))
+        {
+            findings.push(Finding::SelfModifying { at, target });
+        }
+    }
+    if rom.len() > platform.max_rom_size() {
+        findings.push(Finding::TooLarge {
+            size: rom.len(),
+            max: platform.max_rom_size(),
+            platform: platform.name,
+        });
+    }
+    findings.sort_by_key(finding_addr);
+    findings.dedup();
+
+    let mut report = Report {
+        size: rom.len(),
+        reachable,
+        findings,
+        suggested_platform: Some(platform),
+    };
+    // a ROM that is too large stays with its platform, the size is reported
+    if report.uses(Extension::XoChip) {
+        report.suggested_platform = None;
+    } else if report.uses(Extension::SuperChip) {
+        report.suggested_platform = Descriptor::find("schip");
+    }
+    report
+}
+
+fn step(
+    opcode: OpCode,
+    at: u16,
+    path: &mut Path,
+    findings: &mut Vec<Finding>,
+    writes: &mut Vec<(u16, u16, u16)>,
+) -> Flow {
+    match opcode {
+        OpCode::SYS { addr } => {
+            findings.push(Finding::MachineCode { at, addr });
+            Flow::Next
+        }
+        OpCode::RET => Flow::Stop,
+        OpCode::JUMP { addr } => Flow::Jump(addr),
+        OpCode::CALL { addr } => Flow::Call(addr),
+        OpCode::JUMPI { .. } => {
+            findings.push(Finding::UnresolvedJump { at });
+            Flow::Stop
+        }
+        OpCode::SKE { .. }
+        | OpCode::SKNE { .. }
+        | OpCode::SKRE { .. }
+        | OpCode::SKRNE { .. }
+        | OpCode::SKP { .. }
+        | OpCode::SKNP { .. } => Flow::Skip,
+        OpCode::LOADI { addr } => {
+            path.i_reg = Some(addr);
+            Flow::Next
+        }
+        OpCode::ADDI { .. } | OpCode::LDSPR { .. } | OpCode::READ { .. } => {
+            path.i_reg = None;
+            Flow::Next
+        }
+        OpCode::STOR { s } => {
+            if let Some(i_reg) = path.i_reg {
+                writes.push((at, i_reg, i_reg + s as u16));
+            }
+            path.i_reg = None;
+            Flow::Next
+        }
+        OpCode::BCD { .. } => {
+            if let Some(i_reg) = path.i_reg {
+                writes.push((at, i_reg, i_reg + 2));
+            }
+            Flow::Next
+        }
+        _ => Flow::Next,
+    }
+}
+
+// SUPER-CHIP and XO-CHIP instructions that plain CHIP-8 does not know
+fn extension(raw: u16) -> Option<Extension> {
+    match raw {
+        0x00FB..=0x00FF => return Some(Extension::SuperChip),
+        0xF000 | 0xF002 => return Some(Extension::XoChip),
+        _ => (),
+    }
+    match raw & 0xF0FF {
+        0xF030 | 0xF075 | 0xF085 => return Some(Extension::SuperChip),
+        0xF001 | 0xF03A => return Some(Extension::XoChip),
+        _ => (),
+    }
+    match raw & 0xFFF0 {
+        0x00C0 => return Some(Extension::SuperChip),
+        0x00D0 => return Some(Extension::XoChip),
+        _ => (),
+    }
+    match raw & 0xF00F {
+        0x5002 | 0x5003 => Some(Extension::XoChip),
+        0xD000 => Some(Extension::SuperChip), // 16x16 sprite
+        _ => None,
+    }
+}
+
+// None for findings about the whole ROM, which come first
+fn finding_addr(finding: &Finding) -> Option<u16> {
+    match finding {
+        Finding::JumpOutsideRom { at, .. }
+        | Finding::UnknownOpcode { at, .. }
+        | Finding::RunsOffEnd { at }
+        | Finding::UnresolvedJump { at }
+        | Finding::MachineCode { at, .. }
+        | Finding::SelfModifying { at, .. }
+        | Finding::Extension { at, .. } => Some(*at),
+        Finding::TooLarge { .. } => None,
+    }
+}
+
+impl fmt::Display for Extension {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Extension::SuperChip => f.write_str("SUPER-CHIP"),
+            Extension::XoChip => f.write_str("XO-CHIP"),
+        }
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Finding::JumpOutsideRom { at, target } => {
+                write!(
+                    f,
+                    "0x{:03X}: jump to 0x{:03X} outside of the ROM",
+                    at, target
+                )
+            }
+            Finding::UnknownOpcode { at, raw } => {
+                write!(f, "0x{:03X}: unknown opcode 0x{:04X}", at, raw)
+            }
+            Finding::RunsOffEnd { at } => write!(f, "0x{:03X}: execution runs off the end", at),
+            Finding::UnresolvedJump { at } => {
+                write!(f, "0x{:03X}: jump target depends on a register", at)
+            }
+            Finding::MachineCode { at, addr } => {
+                write!(f, "0x{:03X}: machine code routine at 0x{:03X}", at, addr)
+            }
+            Finding::SelfModifying { at, target } => {
+                write!(f, "0x{:03X}: writes into code at 0x{:03X}", at, target)
+            }
+            Finding::Extension { at, raw, extension } => {
+                write!(f, "0x{:03X}: {} instruction 0x{:04X}", at, extension, raw)
+            }
+            Finding::TooLarge {
+                size,
+                max,
+                platform,
+            } => write!(
+                f,
+                "ROM is {} bytes, {} programs can be at most {} bytes",
+                size, platform, max
+            ),
+        }
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Size: {} bytes", self.size)?;
+        writeln!(f, "Reachable instructions: {}", self.reachable.len())?;
+        let extensions: Vec<String> = [Extension::SuperChip, Extension::XoChip]
+            .iter()
+            .filter(|extension| self.uses(**extension))
+            .map(|extension| extension.to_string())
+            .collect();
+        if extensions.is_empty() {
+            writeln!(f, "Extensions: none")?;
+        } else {
+            writeln!(f, "Extensions: {}", extensions.join(", "))?;
+        }
+        match self.suggested_platform {
+            Some(platform) => writeln!(f, "Suggested platform: {}", platform)?,
+            None => writeln!(f, "Suggested platform: XO-CHIP, which is not supported")?,
+        }
+        for finding in &self.findings {
+            writeln!(f, "  {}", finding)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,111 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::database::{Database, RomInfo};
+use super::rom::Rom;
+
+// larger files are listed without looking them up in the database
+const MAX_HASHED_SIZE: u64 = 0x10000;
+
+pub struct RomEntry {
+    pub name: String,
+    pub path: PathBuf,
+    pub size: u64,
+    pub info: Option<RomInfo>,
+}
+
+pub struct RomBrowser {
+    dir: PathBuf,
+    entries: Vec<RomEntry>,
+    selected: usize,
+    message: Option<String>,
+}
+
+impl RomBrowser {
+    pub fn scan<P: AsRef<Path>>(dir: P, database: &Database) -> io::Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        let mut entries = Vec::new();
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            if !metadata.is_file() {
+                continue;
+            }
+            let info = if metadata.len() <= MAX_HASHED_SIZE {
+                Rom::load(entry.path())
+                    .ok()
+                    .and_then(|rom| database.lookup(&rom.sha1()))
+            } else {
+                None
+            };
+            entries.push(RomEntry {
+                name: entry.file_name().to_string_lossy().into_owned(),
+                path: entry.path(),
+                size: metadata.len(),
+                info,
+            });
+        }
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(RomBrowser {
+            dir,
+            entries,
+            selected: 0,
+            message: None,
+        })
+    }
+
+    pub fn selected(&self) -> Option<&RomEntry> {
+        self.entries.get(self.selected)
+    }
+
+    pub fn select_path(&mut self, path: &Path) {
+        if let Some(index) = self.entries.iter().position(|e| e.path == path) {
+            self.selected = index;
+        }
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn select_next(&mut self) {
+        if self.selected + 1 < self.entries.len() {
+            self.selected += 1;
+        }
+    }
+
+    pub fn select_first(&mut self) {
+        self.selected = 0;
+    }
+
+    pub fn select_last(&mut self) {
+        self.selected = self.entries.len().saturating_sub(1);
+    }
+
+    pub fn set_message(&mut self, message: String) {
+        self.message = Some(message);
+    }
+
+    pub fn clear_message(&mut self) {
+        self.message = None;
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    // sorted by file name
+    pub fn entries(&self) -> &[RomEntry] {
+        &self.entries
+    }
+
+    pub fn selected_index(&self) -> usize {
+        self.selected
+    }
+
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+}
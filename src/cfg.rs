@@ -0,0 +1,341 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use super::memory_map::PROGRAM_START;
+use super::opcode::{self, OpCode};
+
+// the end of the address space, where no instruction can start
+const END_OF_MEMORY: usize = 0x10000;
+
+// targets of an instruction, None when unresolved; addresses may go past the
+// end of the address space
+type Successors = Vec<(Option<u32>, EdgeKind)>;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum GraphFormat {
+    Dot, // Graphviz
+    Json,
+}
+
+impl GraphFormat {
+    pub const NAMES: &'static [&'static str] = &["dot", "json"];
+}
+
+impl FromStr for GraphFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "dot" => Ok(GraphFormat::Dot),
+            "json" => Ok(GraphFormat::Json),
+            _ => Err(format!(
+                "unknown graph format {:?}, expected one of {}",
+                s,
+                GraphFormat::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EdgeKind {
+    Fallthrough,
+    Jump,
+    SkipTaken,    // condition held, the next instruction is skipped
+    SkipNotTaken, // condition failed, execution continues with the next one
+    Call,
+    CallReturn, // from a call to the instruction following it
+    Unresolved, // JUMPI, the target depends on V0 at run time
+}
+
+#[derive(Serialize)]
+pub struct Instruction {
+    pub addr: u16,
+    pub raw: u16,
+    pub text: String,
+}
+
+#[derive(Serialize)]
+pub struct Block {
+    pub start: u16,
+    pub instructions: Vec<Instruction>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Target {
+    Block(u16),      // start of the target block
+    OutsideRom(u32), // an address the ROM does not cover, possibly past the end of memory
+    Unresolved,      // JUMPI, the target depends on V0 at run time
+}
+
+#[derive(Serialize)]
+pub struct Edge {
+    pub from: u16, // start of the source block
+    pub to: Target,
+    pub kind: EdgeKind,
+}
+
+#[derive(Serialize)]
+pub struct Subroutine {
+    pub entry: u16,
+    pub calls: BTreeSet<u16>, // entries of the subroutines called from here
+}
+
+#[derive(Serialize)]
+pub struct Cfg {
+    pub blocks: Vec<Block>,
+    pub edges: Vec<Edge>,
+    pub subroutines: Vec<Subroutine>, // the program entry at 0x200 comes first
+}
+
+// Recursive-descent pass from 0x200. Only targets inside the ROM are
+// followed; edges to anywhere else end outside of the ROM.
+pub fn build(rom: &[u8]) -> Cfg {
+    let end = (PROGRAM_START as usize + rom.len()).min(END_OF_MEMORY);
+    let in_rom = |addr: u32| addr >= PROGRAM_START as u32 && (addr as usize) + 1 < end;
+
+    // instruction address -> (raw value, successors)
+    let mut code: BTreeMap<u16, (u16, Successors)> = BTreeMap::new();
+    let mut work = vec![PROGRAM_START];
+    while let Some(addr) = work.pop() {
+        if code.contains_key(&addr) || !in_rom(addr as u32) {
+            continue;
+        }
+        let index = (addr - PROGRAM_START) as usize;
+        let raw = (rom[index] as u16) << 8 | rom[index + 1] as u16;
+        let successors = successors(addr, raw);
+        work.extend(inside(&successors, in_rom));
+        code.insert(addr, (raw, successors));
+    }
+
+    // a block ends after any instruction that does not simply fall through
+    // and starts at any address that is the target of such an instruction
+    let falls_through = |successors: &Successors, addr: u16| {
+        successors.len() == 1 && successors[0] == (Some(addr as u32 + 2), EdgeKind::Fallthrough)
+    };
+    let mut leaders = BTreeSet::new();
+    leaders.insert(PROGRAM_START);
+    for (&addr, (_, successors)) in &code {
+        if !falls_through(successors, addr) {
+            leaders.extend(inside(successors, in_rom));
+        }
+    }
+
+    let mut blocks: Vec<Block> = Vec::new();
+    let mut block_of = BTreeMap::new();
+    let mut previous: Option<u16> = None;
+    for (&addr, (raw, _)) in &code {
+        let continues = previous.is_some_and(|prev| {
+            prev as u32 + 2 == addr as u32
+                && !leaders.contains(&addr)
+                && falls_through(&code[&prev].1, prev)
+        });
+        if !continues {
+            blocks.push(Block {
+                start: addr,
+                instructions: Vec::new(),
+            });
+        }
+        let block = blocks.last_mut().unwrap();
+        block_of.insert(addr, block.start);
+        block.instructions.push(Instruction {
+            addr,
+            raw: *raw,
+            text: opcode::decode(*raw).to_string(),
+        });
+        previous = Some(addr);
+    }
+
+    let mut edges = Vec::new();
+    for block in &blocks {
+        let last = block.instructions.last().unwrap().addr;
+        for &(to, kind) in &code[&last].1 {
+            edges.push(Edge {
+                from: block.start,
+                to: match to {
+                    Some(to) if in_rom(to) => Target::Block(block_of[&(to as u16)]),
+                    Some(to) => Target::OutsideRom(to),
+                    None => Target::Unresolved,
+                },
+                kind,
+            });
+        }
+    }
+
+    let mut entries = vec![PROGRAM_START];
+    entries.extend(
+        edges
+            .iter()
+            .filter_map(|edge| match (edge.kind, edge.to) {
+                (EdgeKind::Call, Target::Block(to)) => Some(to),
+                _ => None,
+            })
+            .collect::<BTreeSet<u16>>(),
+    );
+    entries.dedup();
+    let subroutines = entries
+        .into_iter()
+        .map(|entry| Subroutine {
+            entry,
+            calls: callees(entry, &edges),
+        })
+        .collect();
+
+    Cfg {
+        blocks,
+        edges,
+        subroutines,
+    }
+}
+
+// the targets a pass can follow, which all fit in 16 bits
+fn inside(successors: &Successors, in_rom: impl Fn(u32) -> bool) -> Vec<u16> {
+    successors
+        .iter()
+        .filter_map(|(to, _)| *to)
+        .filter(|to| in_rom(*to))
+        .map(|to| to as u16)
+        .collect()
+}
+
+fn successors(addr: u16, raw: u16) -> Successors {
+    let next = addr as u32 + 2;
+    match opcode::decode(raw) {
+        OpCode::Unknown(_) | OpCode::RET => vec![],
+        OpCode::JUMP { addr } => vec![(Some(addr as u32), EdgeKind::Jump)],
+        OpCode::CALL { addr } => vec![
+            (Some(addr as u32), EdgeKind::Call),
+            (Some(next), EdgeKind::CallReturn),
+        ],
+        OpCode::JUMPI { .. } => vec![(None, EdgeKind::Unresolved)],
+        OpCode::SKE { .. }
+        | OpCode::SKNE { .. }
+        | OpCode::SKRE { .. }
+        | OpCode::SKRNE { .. }
+        | OpCode::SKP { .. }
+        | OpCode::SKNP { .. } => vec![
+            (Some(next), EdgeKind::SkipNotTaken),
+            (Some(next + 2), EdgeKind::SkipTaken),
+        ],
+        _ => vec![(Some(next), EdgeKind::Fallthrough)],
+    }
+}
+
+// subroutines called from the blocks reachable from entry without calling
+fn callees(entry: u16, edges: &[Edge]) -> BTreeSet<u16> {
+    let mut calls = BTreeSet::new();
+    let mut visited = BTreeSet::new();
+    let mut work = vec![entry];
+    while let Some(block) = work.pop() {
+        if !visited.insert(block) {
+            continue;
+        }
+        for edge in edges.iter().filter(|edge| edge.from == block) {
+            match (edge.kind, edge.to) {
+                (EdgeKind::Call, Target::Block(to)) => {
+                    calls.insert(to);
+                }
+                (_, Target::Block(to)) => work.push(to),
+                _ => (),
+            }
+        }
+    }
+    calls
+}
+
+impl Cfg {
+    pub fn export(&self, format: GraphFormat) -> String {
+        match format {
+            GraphFormat::Dot => self.to_dot(),
+            GraphFormat::Json => self.to_json(),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Could not serialize control-flow graph")
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::new();
+        // writing to a String cannot fail
+        writeln!(dot, "digraph cfg {{").unwrap();
+        writeln!(dot, "    node [shape=box fontname=monospace];").unwrap();
+        for subroutine in &self.subroutines {
+            writeln!(
+                dot,
+                "    sub_{0:03X} [label=\"sub 0x{0:03X}\" shape=ellipse];",
+                subroutine.entry
+            )
+            .unwrap();
+            for callee in &subroutine.calls {
+                writeln!(
+                    dot,
+                    "    sub_{:03X} -> sub_{:03X} [style=dotted];",
+                    subroutine.entry, callee
+                )
+                .unwrap();
+            }
+            writeln!(dot, "    sub_{0:03X} -> block_{0:03X};", subroutine.entry).unwrap();
+        }
+        for block in &self.blocks {
+            let mut label = String::new();
+            for instruction in &block.instructions {
+                write!(label, "0x{:03X}: {}\\l", instruction.addr, instruction.text).unwrap();
+            }
+            writeln!(dot, "    block_{:03X} [label=\"{}\"];", block.start, label).unwrap();
+        }
+        let mut unresolved = false;
+        let mut outside = BTreeSet::new();
+        for edge in &self.edges {
+            let style = match edge.kind {
+                EdgeKind::Fallthrough => "",
+                EdgeKind::Jump => " [color=blue]",
+                EdgeKind::SkipTaken => " [color=darkgreen label=skip]",
+                EdgeKind::SkipNotTaken => " [color=red]",
+                EdgeKind::Call => " [style=bold label=call]",
+                EdgeKind::CallReturn => " [style=dashed]",
+                EdgeKind::Unresolved => " [style=dashed color=gray]",
+            };
+            match edge.to {
+                Target::Block(to) => {
+                    writeln!(
+                        dot,
+                        "    block_{:03X} -> block_{:03X}{};",
+                        edge.from, to, style
+                    )
+                }
+                Target::OutsideRom(to) => {
+                    outside.insert(to);
+                    writeln!(
+                        dot,
+                        "    block_{:03X} -> outside_{:03X}{};",
+                        edge.from, to, style
+                    )
+                }
+                Target::Unresolved => {
+                    unresolved = true;
+                    writeln!(dot, "    block_{:03X} -> unresolved{};", edge.from, style)
+                }
+            }
+            .unwrap();
+        }
+        for to in outside {
+            writeln!(
+                dot,
+                "    outside_{0:03X} [label=\"0x{0:03X}\" shape=octagon];",
+                to
+            )
+            .unwrap();
+        }
+        if unresolved {
+            writeln!(dot, "    unresolved [label=\"?\" shape=diamond];").unwrap();
+        }
+        writeln!(dot, "}}").unwrap();
+        dot
+    }
+}
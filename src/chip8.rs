@@ -1,9 +1,22 @@
+#[cfg(feature = "dynarec")]
+mod dynarec;
+
 use rand::prelude::*;
+use rand::rngs::StdRng;
 
+use super::fault::Fault;
+use super::font::Font;
 use super::keys::Keys;
 use super::mem::Memory;
-use super::rom::Rom;
+use super::memory_map::{Access, MemoryMap, Violation, FONT_START};
+use super::platform::Descriptor;
+use super::policy::{OpcodePolicy, Trap};
+use super::quirks::{IndexIncrement, Quirks};
+use super::rom::{Rom, RomError};
 use super::screen_buffer::ScreenBuffer;
+use super::stack::{Frame, Stack};
+use super::state::{Reader, StateError, Writer};
+use super::timing::{self, Timing, VIP_FRAME_CYCLES, VIP_INTERRUPT_CYCLES};
 
 use super::opcode;
 use super::opcode::OpCode;
@@ -11,40 +24,29 @@ use super::opcode::OpCode;
 pub const SCREEN_WIDTH: u16 = 64;
 pub const SCREEN_HEIGHT: u16 = 32;
 
-const FONT_START_OFFSET: u16 = 0x050;
-const ROM_START_OFFSET: u16 = 0x200;
-const REGISTERS: usize = 16;
-const STACK_SIZE: usize = 16;
+pub const REGISTERS: usize = 16;
 const REG_F: usize = 0xF;
 const DEFAULT_PC_INC: u16 = 2;
-
-#[cfg_attr(rustfmt, rustfmt_skip)]
-static FONT_DATA: &'static [u8] =
-&[
-  0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
-  0x20, 0x60, 0x20, 0x20, 0x70, // 1
-  0xF0, 0x10, 0xF0, 0x80, 0xF0, // 2
-  0xF0, 0x10, 0xF0, 0x10, 0xF0, // 3
-  0x90, 0x90, 0xF0, 0x10, 0x10, // 4
-  0xF0, 0x80, 0xF0, 0x10, 0xF0, // 5
-  0xF0, 0x80, 0xF0, 0x90, 0xF0, // 6
-  0xF0, 0x10, 0x20, 0x40, 0x40, // 7
-  0xF0, 0x90, 0xF0, 0x90, 0xF0, // 8
-  0xF0, 0x90, 0xF0, 0x10, 0xF0, // 9
-  0xF0, 0x90, 0xF0, 0x90, 0x90, // A
-  0xE0, 0x90, 0xE0, 0x90, 0xE0, // B
-  0xF0, 0x80, 0x80, 0x80, 0xF0, // C
-  0xE0, 0x90, 0x90, 0x90, 0xE0, // D
-  0xF0, 0x80, 0xF0, 0x80, 0xF0, // E
-  0xF0, 0x80, 0xF0, 0x80, 0x80  // F
-];
+const ADDR_MASK: u16 = 0xFFF;
+const MEMORY_SIZE: usize = 0x1000;
+
+// the machine state visible to a program, for comparing runs
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub regs: [u8; REGISTERS],
+    pub i_reg: u16,
+    pub pc: u16,
+    pub stack: Vec<u16>, // return addresses of the active frames, innermost last
+    pub delay_timer: u8,
+    pub sound_timer: u8,
+    pub screen: Vec<bool>, // row by row, as wide as the screen of the platform
+}
 
 pub struct Chip8 {
     memory: Memory,
     regs: [u8; REGISTERS],
-    stack: [u16; STACK_SIZE],
+    stack: Stack,
     screen_buffer: ScreenBuffer,
-    sp: u16,    // stack pointer
     pc: u16,    // program counter
     i_reg: u16, // index register
     keys: Keys,
@@ -52,16 +54,45 @@ pub struct Chip8 {
     delay_timer: u8,
     sound_timer: u8,
     step: u32,
+    timing: Timing,
+    cycle_balance: i64, // machine cycles left in the frame, negative when overrun
+    draws: u32,         // sprites drawn since the last frame started
+    frame: u32,         // frames since the reset, counted by the timer ticks
+    platform: Descriptor,
+    quirks: Quirks,
+    font: Font,
+    rng: StdRng,
+    fault: Option<Fault>, // set once the machine halted
+    sys_policy: OpcodePolicy,
+    unknown_policy: OpcodePolicy,
+    trap: Option<Trap>,
+    warnings: u32, // opcodes skipped under OpcodePolicy::Warn
+    decode_cache: bool,
+    memory_map: MemoryMap,
+    check_memory: bool,
+    violations: Vec<Violation>, // accesses the memory map does not allow
+    #[cfg(feature = "dynarec")]
+    dynarec: bool,
+    #[cfg(feature = "dynarec")]
+    blocks: dynarec::BlockCache,
+}
+
+impl Default for Chip8 {
+    fn default() -> Self {
+        Chip8::new()
+    }
 }
 
 impl Chip8 {
     pub fn new() -> Self {
+        let quirks = Quirks::default();
+        let platform = Descriptor::default();
+        let font = Font::builtin(quirks.font);
         Chip8 {
             memory: Memory::new(),
             regs: [0; REGISTERS],
-            stack: [0; STACK_SIZE],
+            stack: Stack::new(quirks.stack_depth, quirks.stack_wraps),
             screen_buffer: ScreenBuffer::new(SCREEN_WIDTH, SCREEN_HEIGHT),
-            sp: 0,
             pc: 0,
             i_reg: 0,
             keys: Keys::new(),
@@ -69,18 +100,251 @@ impl Chip8 {
             delay_timer: 0,
             sound_timer: 0,
             step: 0,
+            timing: Timing::default(),
+            cycle_balance: 0,
+            draws: 0,
+            frame: 0,
+            memory_map: platform.memory_map(&font),
+            platform,
+            font,
+            quirks,
+            rng: StdRng::from_entropy(),
+            fault: None,
+            sys_policy: OpcodePolicy::Warn,
+            unknown_policy: OpcodePolicy::Halt,
+            trap: None,
+            warnings: 0,
+            decode_cache: true,
+            check_memory: false,
+            violations: Vec::new(),
+            #[cfg(feature = "dynarec")]
+            dynarec: false,
+            #[cfg(feature = "dynarec")]
+            blocks: dynarec::BlockCache::new(),
         }
     }
 
+    pub fn reset(&mut self) {
+        self.memory = Memory::new();
+        self.regs = [0; REGISTERS];
+        self.stack.clear();
+        self.screen_buffer.clear();
+        self.pc = 0;
+        self.i_reg = 0;
+        self.keys = Keys::new();
+        self.redraw = true;
+        self.delay_timer = 0;
+        self.sound_timer = 0;
+        self.step = 0;
+        self.cycle_balance = 0;
+        self.draws = 0;
+        self.frame = 0;
+        self.fault = None;
+        self.warnings = 0;
+        self.violations.clear();
+        #[cfg(feature = "dynarec")]
+        self.blocks.clear();
+    }
+
     pub fn init(&mut self) {
-        self.pc = ROM_START_OFFSET;
-        self.memory.load_data(FONT_DATA, FONT_START_OFFSET);
-        println!("CHIP-8 init",);
+        self.pc = self.platform.load_address;
+        self.memory.load_data(self.font.small(), FONT_START);
+        if let Some(big) = self.font.big() {
+            let big_font_start = FONT_START + self.font.small().len() as u16;
+            self.memory.load_data(big, big_font_start);
+        }
     }
 
-    pub fn load_rom(&mut self, rom: Rom) {
+    pub fn load_rom(&mut self, rom: Rom) -> Result<(), RomError> {
+        rom.validate_for(&self.platform)?;
         let rom_data: Vec<u8> = rom.into();
-        self.memory.load_data(&rom_data, ROM_START_OFFSET);
+        self.memory.load_data(&rom_data, self.platform.load_address);
+        Ok(())
+    }
+
+    // load address, screen size, memory map and quirks; takes effect on init
+    pub fn set_platform(&mut self, platform: &Descriptor) {
+        self.platform = platform.clone();
+        self.screen_buffer = ScreenBuffer::new(platform.screen_width, platform.screen_height);
+        self.redraw = true;
+        self.set_quirks(platform.quirks());
+    }
+
+    pub fn platform(&self) -> &Descriptor {
+        &self.platform
+    }
+
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        self.stack.set_depth(quirks.stack_depth, quirks.stack_wraps);
+        self.quirks = quirks;
+        self.set_font(Font::builtin(quirks.font));
+    }
+
+    // replaces the font of the quirks, takes effect on init
+    pub fn set_font(&mut self, font: Font) {
+        self.memory_map = self.platform.memory_map(&font);
+        self.font = font;
+    }
+
+    // for 0nnn machine code routines
+    pub fn set_sys_policy(&mut self, policy: OpcodePolicy) {
+        self.sys_policy = policy;
+    }
+
+    // for values that are no instruction
+    pub fn set_unknown_policy(&mut self, policy: OpcodePolicy) {
+        self.unknown_policy = policy;
+    }
+
+    // handler for the opcodes whose policy is OpcodePolicy::Trap
+    pub fn set_trap(&mut self, trap: impl FnMut(&mut Chip8, u16) -> bool + 'static) {
+        self.trap = Some(Box::new(trap));
+    }
+
+    // until the platform, the quirks or the font change
+    pub fn set_memory_map(&mut self, memory_map: MemoryMap) {
+        self.memory_map = memory_map;
+    }
+
+    pub fn memory_map(&self) -> &MemoryMap {
+        &self.memory_map
+    }
+
+    // records memory accesses the memory map does not allow, for debuggers
+    pub fn set_check_memory(&mut self, check: bool) {
+        self.check_memory = check;
+    }
+
+    pub fn take_violations(&mut self) -> Vec<Violation> {
+        std::mem::take(&mut self.violations)
+    }
+
+    pub fn warnings(&self) -> u32 {
+        self.warnings
+    }
+
+    // decoding every instruction again is only kept around for comparison
+    pub fn set_decode_cache(&mut self, enabled: bool) {
+        self.decode_cache = enabled;
+    }
+
+    // run batches of instructions as compiled blocks instead of one by one
+    #[cfg(feature = "dynarec")]
+    pub fn set_dynarec(&mut self, enabled: bool) {
+        self.dynarec = enabled;
+    }
+
+    pub fn seed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        let screen = &self.screen_buffer;
+        Snapshot {
+            regs: self.regs,
+            i_reg: self.i_reg,
+            pc: self.pc,
+            stack: self
+                .stack
+                .frames()
+                .iter()
+                .map(|frame| frame.return_addr)
+                .collect(),
+            delay_timer: self.delay_timer,
+            sound_timer: self.sound_timer,
+            screen: (0..screen.height())
+                .flat_map(|y| (0..screen.width()).map(move |x| screen.get_pixel(x, y)))
+                .collect(),
+        }
+    }
+
+    // memory, registers, stack, timers and screen; keys and settings are not
+    // part of a save state
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut state = Writer::new();
+        state.bytes(self.memory.data());
+        state.bytes(&self.regs);
+        state.u16(self.i_reg);
+        state.u16(self.pc);
+        let (frames, sp) = self.stack.entries();
+        // an unlimited stack can grow past what 16 bits count
+        state.u32(frames.len() as u32);
+        state.u32(sp as u32);
+        for frame in frames {
+            state.u16(frame.call_site);
+            state.u16(frame.return_addr);
+        }
+        state.u8(self.delay_timer);
+        state.u8(self.sound_timer);
+        let screen = &self.screen_buffer;
+        state.bits(
+            (0..screen.height())
+                .flat_map(|y| (0..screen.width()).map(move |x| screen.get_pixel(x, y))),
+        );
+        state.finish()
+    }
+
+    // the machine is left alone unless the whole state is valid
+    pub fn load_state(&mut self, data: &[u8]) -> Result<(), StateError> {
+        let mut state = Reader::new(data)?;
+        let memory = state.bytes(MEMORY_SIZE)?;
+        let mut regs = [0; REGISTERS];
+        regs.copy_from_slice(state.bytes(REGISTERS)?);
+        let i_reg = state.u16()?;
+        let pc = state.u16()?;
+        let entries = state.u32()? as usize;
+        let sp = state.u32()? as usize;
+        let mut frames = Vec::new();
+        for _ in 0..entries {
+            frames.push(Frame {
+                call_site: state.u16()?,
+                return_addr: state.u16()?,
+            });
+        }
+        let delay_timer = state.u8()?;
+        let sound_timer = state.u8()?;
+        let (width, height) = (self.screen_buffer.width(), self.screen_buffer.height());
+        let pixels = state.bits(width as usize * height as usize)?;
+        state.finish()?;
+
+        if i_reg > ADDR_MASK {
+            return Err(StateError::Invalid("index register"));
+        }
+        if self.stack.depth().is_some_and(|depth| entries > depth) {
+            return Err(StateError::Invalid("stack depth"));
+        }
+        if sp > entries {
+            return Err(StateError::Invalid("stack pointer"));
+        }
+
+        self.memory = Memory::new();
+        self.memory.load_data(memory, 0);
+        self.regs = regs;
+        self.i_reg = i_reg;
+        self.pc = pc;
+        self.stack.restore(frames, sp);
+        self.delay_timer = delay_timer;
+        self.sound_timer = sound_timer;
+        self.screen_buffer.clear();
+        for (index, _) in pixels.iter().enumerate().filter(|(_, pixel)| **pixel) {
+            let index = index as u16;
+            self.screen_buffer.xor(index % width, index / width);
+        }
+        self.redraw = true;
+        self.fault = None;
+        #[cfg(feature = "dynarec")]
+        self.blocks.clear();
+        Ok(())
+    }
+
+    // the active subroutine calls, innermost last
+    pub fn call_stack(&self) -> &[Frame] {
+        self.stack.frames()
+    }
+
+    // why the machine halted, if it did
+    pub fn fault(&self) -> Option<Fault> {
+        self.fault
     }
 
     pub fn screen_buffer(&self) -> &ScreenBuffer {
@@ -103,10 +367,12 @@ impl Chip8 {
         if self.redraw {
             self.redraw = false;
         }
+        if self.fault.is_some() {
+            return;
+        }
 
-        self.step += 1;
-        let opcode_raw = self.fetch_opcode();
-        let opcode = opcode::decode(opcode_raw);
+        self.step = self.step.wrapping_add(1);
+        let opcode = self.next_opcode();
         //println!("Step {:?}: Fetched opcode {:?} at PC 0x{:X}",
         //         self.step,
         //         &opcode,
@@ -116,7 +382,125 @@ impl Chip8 {
         // which allows overwrite of the pc in opcode execution
         self.pc += DEFAULT_PC_INC;
         self.execute_opcode(opcode);
+    }
+
+    fn next_opcode(&mut self) -> OpCode {
+        // jumps and skips may have left the PC past the end of memory
+        self.pc &= ADDR_MASK;
+        if self.decode_cache {
+            self.memory.decode(self.pc)
+        } else {
+            opcode::decode(self.fetch_opcode())
+        }
+    }
+
+    pub fn set_timing(&mut self, timing: Timing) {
+        self.timing = timing;
+        self.cycle_balance = 0;
+    }
+
+    pub fn timing(&self) -> Timing {
+        self.timing
+    }
 
+    // Runs the instructions of one 60 Hz frame as the timing allows and then
+    // the display interrupt, which counts down the timers. With the display
+    // wait quirk the frame ends early after a sprite is drawn. Returns how
+    // many instructions ran.
+    pub fn run_frame(&mut self) -> u32 {
+        let start = self.step;
+        let display_wait = self.quirks.display_wait;
+        self.draws = 0;
+        match self.timing {
+            Timing::Fixed(count) => self.run_instructions(count, display_wait),
+            Timing::Vip => self.run_vip_cycles(display_wait),
+        }
+        self.tick_timers();
+        self.step.wrapping_sub(start)
+    }
+
+    // sprites drawn in the last frame, or since it if it is still running
+    pub fn draws(&self) -> u32 {
+        self.draws
+    }
+
+    pub fn frame(&self) -> u32 {
+        self.frame
+    }
+
+    fn waiting_for_frame(&self, display_wait: bool) -> bool {
+        display_wait && self.draws > 0
+    }
+
+    // an instruction running past the end of the frame delays the next one
+    fn run_vip_cycles(&mut self, display_wait: bool) {
+        let mut redraw = false;
+        self.cycle_balance =
+            self.cycle_balance.min(0) + (VIP_FRAME_CYCLES - VIP_INTERRUPT_CYCLES) as i64;
+        while self.cycle_balance > 0
+            && self.fault.is_none()
+            && !self.waiting_for_frame(display_wait)
+        {
+            let opcode = self.next_opcode();
+            let (pc, regs) = (self.pc, self.regs);
+            self.run_cycle();
+            let skipped = self.pc & ADDR_MASK == pc.wrapping_add(2 * DEFAULT_PC_INC) & ADDR_MASK;
+            self.cycle_balance -= timing::vip_cycles(opcode, &regs, skipped) as i64;
+            redraw |= self.redraw;
+        }
+        self.redraw = redraw;
+    }
+
+    // afterwards redraw() tells whether any of the instructions changed the screen
+    pub fn run_cycles(&mut self, count: u32) {
+        self.run_instructions(count, false);
+    }
+
+    fn run_instructions(&mut self, count: u32, display_wait: bool) {
+        #[cfg(feature = "dynarec")]
+        {
+            if self.dynarec {
+                return self.run_blocks(count, display_wait);
+            }
+        }
+        let mut redraw = false;
+        for _ in 0..count {
+            if self.waiting_for_frame(display_wait) {
+                break;
+            }
+            self.run_cycle();
+            redraw |= self.redraw;
+        }
+        self.redraw = redraw;
+    }
+
+    #[cfg(feature = "dynarec")]
+    fn run_blocks(&mut self, count: u32, display_wait: bool) {
+        let mut redraw = false;
+        let mut remaining = count as usize;
+        // DRAW ends a block, so waiting only needs checking in between
+        while remaining > 0 && self.fault.is_none() && !self.waiting_for_frame(display_wait) {
+            self.pc &= ADDR_MASK;
+            let block = self.blocks.get(self.pc, &mut self.memory);
+            if block.len() == 0 {
+                // not compilable, let the interpreter deal with it
+                self.run_cycle();
+                redraw |= self.redraw;
+                remaining -= 1;
+                continue;
+            }
+            self.redraw = false;
+            let count = block.run(self, block.len().min(remaining));
+            self.step = self.step.wrapping_add(count as u32);
+            redraw |= self.redraw;
+            remaining -= count;
+        }
+        self.redraw = redraw;
+    }
+
+    // the timers count down at 60 Hz, independent of the instructions per frame
+    pub fn tick_timers(&mut self) {
+        self.frame = self.frame.wrapping_add(1);
         if self.delay_timer > 0 {
             self.delay_timer -= 1;
         }
@@ -126,11 +510,10 @@ impl Chip8 {
     }
 
     fn fetch_opcode(&self) -> u16 {
-        let p1 = (self.memory.get(self.pc) as u16) << 8;
-        let p2 = self.memory.get(self.pc + 1) as u16;
-        p1 | p2
+        self.memory.get_u16(self.pc)
     }
 
+    #[inline]
     fn execute_opcode(&mut self, opcode: OpCode) {
         match opcode {
             OpCode::SYS { addr } => self.sys(addr),
@@ -149,8 +532,9 @@ impl Chip8 {
             OpCode::XOR { s, t } => self.xor(s, t),
             OpCode::ADDR { s, t } => self.addr(s, t),
             OpCode::SUB { s, t } => self.sub(s, t),
-            OpCode::SHR { s } => self.shr(s),
-            OpCode::SHL { s } => self.shl(s),
+            OpCode::SHR { s, t } => self.shr(s, t),
+            OpCode::SUBN { s, t } => self.subn(s, t),
+            OpCode::SHL { s, t } => self.shl(s, t),
             OpCode::SKRNE { s, t } => self.skrne(s, t),
             OpCode::LOADI { addr } => self.loadi(addr),
             OpCode::JUMPI { addr } => self.jumpi(addr),
@@ -167,12 +551,82 @@ impl Chip8 {
             OpCode::BCD { s } => self.bcd(s),
             OpCode::STOR { s } => self.stor(s),
             OpCode::READ { s } => self.read(s),
+            OpCode::Unknown(raw) => self.unknown(raw),
             // _ => panic!("opcode {:?} not implemented yet", opcode),
         };
     }
 
-    fn sys(&self, addr: u16) {
-        println!("Ignoring opcode SYS to addr {:?}", addr);
+    fn sys(&mut self, addr: u16) {
+        let fault = |at| Fault::MachineCode {
+            addr: at,
+            target: addr,
+        };
+        self.unsupported(self.sys_policy, addr, fault);
+    }
+
+    fn unknown(&mut self, raw: u16) {
+        let fault = |addr| Fault::InvalidOpcode { addr, raw };
+        self.unsupported(self.unknown_policy, raw, fault);
+    }
+
+    // neither can be executed, the policy decides what happens instead
+    fn unsupported(&mut self, policy: OpcodePolicy, raw: u16, fault: impl Fn(u16) -> Fault) {
+        let resume = match policy {
+            OpcodePolicy::Ignore => true,
+            OpcodePolicy::Warn => {
+                self.warnings = self.warnings.saturating_add(1);
+                true
+            }
+            OpcodePolicy::Halt => false,
+            // the handler gets the whole machine, so it is taken out meanwhile
+            OpcodePolicy::Trap => match self.trap.take() {
+                Some(mut trap) => {
+                    let resume = trap(self, raw);
+                    self.trap.get_or_insert(trap);
+                    resume
+                }
+                None => false,
+            },
+        };
+        if !resume {
+            self.halt(fault);
+        }
+    }
+
+    // leaves the PC on the instruction that faulted
+    fn load_byte(&mut self, addr: u16) -> u8 {
+        self.check_access(addr, Access::Read);
+        self.memory.get(addr)
+    }
+
+    fn store_byte(&mut self, addr: u16, value: u8) {
+        self.check_access(addr, Access::Write);
+        self.memory.set(addr, value);
+    }
+
+    // addr is unmasked, so accesses running past the end of memory show up
+    fn check_access(&mut self, addr: u16, access: Access) {
+        if !self.check_memory {
+            return;
+        }
+        if let Err(region) = self.memory_map.check(addr as usize, access) {
+            self.violations.push(Violation {
+                addr: self.instruction_addr(),
+                target: addr as usize,
+                access,
+                region,
+            });
+        }
+    }
+
+    // the instruction being executed, the PC already points past it
+    fn instruction_addr(&self) -> u16 {
+        self.pc.wrapping_sub(DEFAULT_PC_INC) & ADDR_MASK
+    }
+
+    fn halt(&mut self, fault: impl Fn(u16) -> Fault) {
+        self.pc -= DEFAULT_PC_INC;
+        self.fault = Some(fault(self.pc));
     }
 
     fn clr(&mut self) {
@@ -181,8 +635,10 @@ impl Chip8 {
     }
 
     fn ret(&mut self) {
-        self.sp -= 1;
-        self.pc = self.stack[self.sp as usize];
+        match self.stack.pop() {
+            Ok(frame) => self.pc = frame.return_addr,
+            Err(_) => self.halt(|addr| Fault::StackUnderflow { addr }),
+        }
     }
 
     fn jump(&mut self, addr: u16) {
@@ -190,9 +646,14 @@ impl Chip8 {
     }
 
     fn call(&mut self, addr: u16) {
-        self.stack[self.sp as usize] = self.pc;
-        self.sp += 1;
-        self.pc = addr;
+        let frame = Frame {
+            call_site: self.instruction_addr(),
+            return_addr: self.pc,
+        };
+        match self.stack.push(frame) {
+            Ok(()) => self.pc = addr,
+            Err(_) => self.halt(|addr| Fault::StackOverflow { addr }),
+        }
     }
 
     fn ske(&mut self, s: u8, nn: u8) {
@@ -232,63 +693,65 @@ impl Chip8 {
     }
 
     fn or(&mut self, s: u8, t: u8) {
-        self.regs[s as usize] = self.regs[s as usize] | self.regs[t as usize];
+        self.regs[s as usize] |= self.regs[t as usize];
+        self.logic_reset_vf();
     }
 
     fn and(&mut self, s: u8, t: u8) {
-        self.regs[s as usize] = self.regs[s as usize] & self.regs[t as usize];
+        self.regs[s as usize] &= self.regs[t as usize];
+        self.logic_reset_vf();
     }
 
     fn xor(&mut self, s: u8, t: u8) {
-        self.regs[s as usize] = self.regs[s as usize] ^ self.regs[t as usize];
+        self.regs[s as usize] ^= self.regs[t as usize];
+        self.logic_reset_vf();
     }
 
+    fn logic_reset_vf(&mut self) {
+        if self.quirks.logic_resets_vf {
+            self.regs[REG_F] = 0;
+        }
+    }
+
+    // The flag instructions below write the result before register F, so
+    // with register F as the destination the flag wins, as on the COSMAC VIP.
+
     fn addr(&mut self, s: u8, t: u8) {
-        let s_val = self.regs[s as usize];
-        let t_val = self.regs[t as usize];
-        // first perform a checked addition
-        self.regs[s as usize] = match s_val.checked_add(t_val) {
-            Some(result) => {
-                // no overflow occured, unset carry flag and return result
-                self.regs[REG_F] = 0;
-                result
-            }
-            None => {
-                // overflow occured, set carry flag and save wrapped result
-                self.regs[REG_F] = 1;
-                s_val.wrapping_add(t_val)
-            }
-        };
+        let (result, carry) = self.regs[s as usize].overflowing_add(self.regs[t as usize]);
+        self.regs[s as usize] = result;
+        self.regs[REG_F] = carry as u8;
     }
 
     fn sub(&mut self, s: u8, t: u8) {
-        let s_val = self.regs[s as usize];
-        let t_val = self.regs[t as usize];
-        // first perform a checked addition
-        self.regs[s as usize] = match s_val.checked_sub(t_val) {
-            Some(result) => {
-                // no overflow occured, unset carry flag and return result
-                self.regs[REG_F] = 1;
-                result
-            }
-            None => {
-                // overflow occured, set carry flag and save wrapped result
-                self.regs[REG_F] = 0;
-                s_val.wrapping_sub(t_val)
-            }
-        };
+        let (result, borrow) = self.regs[s as usize].overflowing_sub(self.regs[t as usize]);
+        self.regs[s as usize] = result;
+        self.regs[REG_F] = !borrow as u8;
+    }
+
+    fn subn(&mut self, s: u8, t: u8) {
+        let (result, borrow) = self.regs[t as usize].overflowing_sub(self.regs[s as usize]);
+        self.regs[s as usize] = result;
+        self.regs[REG_F] = !borrow as u8;
     }
 
-    fn shr(&mut self, s: u8) {
-        let s_val = self.regs[s as usize];
+    fn shr(&mut self, s: u8, t: u8) {
+        let s_val = self.shift_source(s, t);
+        self.regs[s as usize] = s_val >> 1;
         self.regs[REG_F] = s_val & 0x1;
-        self.regs[s as usize] >>= 1;
     }
 
-    fn shl(&mut self, s: u8) {
-        let s_val = self.regs[s as usize];
+    fn shl(&mut self, s: u8, t: u8) {
+        let s_val = self.shift_source(s, t);
+        self.regs[s as usize] = s_val << 1;
         self.regs[REG_F] = s_val >> 7;
-        self.regs[s as usize] <<= 1;
+    }
+
+    fn shift_source(&self, s: u8, t: u8) -> u8 {
+        if self.quirks.shift_uses_vy {
+            self.regs[t as usize]
+        } else {
+            self.regs[s as usize]
+        }
     }
 
     fn loadi(&mut self, addr: u16) {
@@ -296,29 +759,41 @@ impl Chip8 {
     }
 
     fn jumpi(&mut self, addr: u16) {
-        self.pc = self.regs[0] as u16 + addr;
+        let reg = if self.quirks.jump_uses_vx {
+            (addr >> 8) as usize
+        } else {
+            0
+        };
+        self.pc = self.regs[reg] as u16 + addr;
     }
 
     fn rand(&mut self, t: u8, nn: u8) {
-        let mut rng = rand::thread_rng();
-        let sample = rng.gen_range(0, nn);
-        self.regs[t as usize] = sample;
+        self.regs[t as usize] = self.rng.gen::<u8>() & nn;
     }
 
     fn draw(&mut self, s: u8, t: u8, n: u8) {
-        let sx = self.regs[s as usize] as u16;
-        let sy = self.regs[t as usize] as u16;
+        let width = self.screen_buffer.width();
+        let height = self.screen_buffer.height();
+        // the start position always wraps; only the sprite itself may be clipped
+        let sx = self.regs[s as usize] as u16 % width;
+        let sy = self.regs[t as usize] as u16 % height;
         // println!("Base Sprite at {:?}, {:?}...", sx, sy);
 
+        self.draws = self.draws.saturating_add(1);
         self.regs[REG_F] = 0;
         for y_line in 0..n {
-            let pixel_row = self.memory.get(self.i_reg + y_line as u16);
+            let pixel_row = self.load_byte(self.i_reg + y_line as u16);
             for x_line in 0..8 {
                 match pixel_row & (0x80 >> x_line) {
                     0 => (),
                     _ => {
                         let x = sx + x_line as u16;
                         let y = sy + y_line as u16;
+                        if self.quirks.clip_sprites && (x >= width || y >= height) {
+                            continue;
+                        }
+                        let x = x % width;
+                        let y = y % height;
                         // println!("Drawing at {:?}, {:?}...", x, y);
                         if self.screen_buffer.xor(x, y) {
                             self.regs[REG_F] = 1;
@@ -331,14 +806,14 @@ impl Chip8 {
     }
 
     fn skp(&mut self, s: u8) {
-        let key = self.regs[s as usize];
+        let key = self.regs[s as usize] & 0xF;
         if self.keys.get(key) {
             self.pc += DEFAULT_PC_INC;
         }
     }
 
     fn sknp(&mut self, s: u8) {
-        let key = self.regs[s as usize];
+        let key = self.regs[s as usize] & 0xF;
         if !self.keys.get(key) {
             self.pc += DEFAULT_PC_INC;
         }
@@ -349,9 +824,11 @@ impl Chip8 {
     }
 
     fn keyd(&mut self, t: u8) {
-        let key = self.regs[t as usize];
-        println!("Waiting for specific key press: {:?}", key);
-        //self.screen.wait_for_key_blocking(key);
+        match (0..16).find(|key| self.keys.get(*key)) {
+            Some(key) => self.regs[t as usize] = key,
+            // wait by running the instruction again
+            None => self.pc -= DEFAULT_PC_INC,
+        }
     }
 
     fn loadd(&mut self, s: u8) {
@@ -367,25 +844,36 @@ impl Chip8 {
     }
 
     fn ldspr(&mut self, s: u8) {
-        self.i_reg = FONT_START_OFFSET + (self.regs[s as usize] as u16 * 5) & 0xFFF;
+        self.i_reg = FONT_START + self.font.glyph(self.regs[s as usize]);
     }
 
     fn bcd(&mut self, s: u8) {
         let vx = self.regs[s as usize];
-        self.memory.set(self.i_reg, (vx / 100) as u8);
-        self.memory.set(self.i_reg + 1, ((vx / 10) % 10) as u8);
-        self.memory.set(self.i_reg + 2, ((vx % 100) % 10) as u8);
+        self.store_byte(self.i_reg, vx / 100);
+        self.store_byte(self.i_reg + 1, (vx / 10) % 10);
+        self.store_byte(self.i_reg + 2, (vx % 100) % 10);
     }
 
     fn stor(&mut self, s: u8) {
         for i in 0..=s as u16 {
-            self.memory.set(self.i_reg + i, self.regs[i as usize]);
+            self.store_byte(self.i_reg + i, self.regs[i as usize]);
         }
+        self.load_store_increment_i(s);
     }
 
     fn read(&mut self, s: u8) {
         for i in 0..=s as u16 {
-            self.regs[i as usize] = self.memory.get(self.i_reg + i);
+            self.regs[i as usize] = self.load_byte(self.i_reg + i);
         }
+        self.load_store_increment_i(s);
+    }
+
+    fn load_store_increment_i(&mut self, s: u8) {
+        let increment = match self.quirks.load_store_index {
+            IndexIncrement::Unchanged => return,
+            IndexIncrement::ByX => s as u16,
+            IndexIncrement::ByXPlusOne => s as u16 + 1,
+        };
+        self.i_reg = (self.i_reg + increment) & 0xFFF;
     }
 }
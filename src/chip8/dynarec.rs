@@ -0,0 +1,191 @@
+use std::rc::Rc;
+
+use super::super::mem::Memory;
+use super::super::opcode::OpCode;
+use super::Chip8;
+
+const MAX_BLOCK_LEN: usize = 64;
+const LAST_ADDR: u16 = 0xFFE; // the last address an instruction fits at
+const MEMORY_SIZE: usize = 0x1000;
+
+// returns false when the op left the block, having set the PC itself
+type Op = Box<dyn Fn(&mut Chip8) -> bool>;
+
+// A run of instructions ending at the first one that may change the PC in a
+// way only known at run time, draw or write to memory. Unconditional jumps are
+// followed and skips leave the block early when taken, so a block is not
+// necessarily contiguous. The PC is only set by ops that leave the block or
+// access memory, and once after the block, not once per op.
+pub struct Block {
+    addrs: Vec<u16>, // address of each op
+    ops: Vec<Op>,
+    end: u16, // PC after running the whole block, unless it jumps
+}
+
+pub struct BlockCache {
+    blocks: Vec<Option<Rc<Block>>>, // indexed by start address
+}
+
+impl BlockCache {
+    pub fn new() -> Self {
+        BlockCache {
+            blocks: vec![None; MEMORY_SIZE],
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.blocks.iter_mut().for_each(|block| *block = None);
+    }
+
+    pub fn get(&mut self, pc: u16, memory: &mut Memory) -> Rc<Block> {
+        // compiling decodes through the memory decode cache, so a write into
+        // any compiled instruction shows up as modified code
+        if memory.take_code_modified() {
+            self.clear();
+        }
+        self.blocks[pc as usize]
+            .get_or_insert_with(|| Rc::new(compile(pc, memory)))
+            .clone()
+    }
+}
+
+impl Block {
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    // runs up to count ops and returns how many were run
+    pub fn run(&self, chip8: &mut Chip8, count: usize) -> usize {
+        for (index, op) in self.ops[..count].iter().enumerate() {
+            if !op(chip8) {
+                return index + 1;
+            }
+        }
+        chip8.pc = match self.addrs.get(count) {
+            Some(addr) => *addr,
+            None => self.end,
+        };
+        count
+    }
+}
+
+fn compile(start: u16, memory: &mut Memory) -> Block {
+    let mut block = Block {
+        addrs: Vec::new(),
+        ops: Vec::new(),
+        end: start,
+    };
+    let mut addr = start;
+    while block.ops.len() < MAX_BLOCK_LEN && addr <= LAST_ADDR {
+        let opcode = memory.decode(addr);
+        block.addrs.push(addr);
+        if let OpCode::JUMP { addr: target } = opcode {
+            // a jump back into the block ends it like any other jump
+            if target <= LAST_ADDR && !block.addrs.contains(&target) {
+                block.ops.push(Box::new(|_| true));
+                addr = target;
+                block.end = target;
+                continue;
+            }
+        }
+        block.ops.push(translate(opcode, addr + 2));
+        addr += 2;
+        block.end = addr;
+        if ends_block(opcode) {
+            break;
+        }
+    }
+    block
+}
+
+fn ends_block(opcode: OpCode) -> bool {
+    matches!(
+        opcode,
+        OpCode::RET
+            | OpCode::JUMP { .. }
+            | OpCode::CALL { .. }
+            | OpCode::JUMPI { .. }
+            | OpCode::KEYD { .. }
+            | OpCode::CLR
+            | OpCode::DRAW { .. }
+            | OpCode::BCD { .. }
+            | OpCode::STOR { .. }
+            | OpCode::SYS { .. }
+            | OpCode::Unknown(_)
+    )
+}
+
+// next is the address following the instruction
+fn translate(opcode: OpCode, next: u16) -> Op {
+    fn op(handler: impl Fn(&mut Chip8) + 'static) -> Op {
+        Box::new(move |c| {
+            handler(c);
+            true
+        })
+    }
+    // the handler sees the PC the interpreter would have set
+    fn branch(next: u16, handler: impl Fn(&mut Chip8) + 'static) -> Op {
+        Box::new(move |c| {
+            c.pc = next;
+            handler(c);
+            false
+        })
+    }
+    // like op, but the handler sees the PC the interpreter would have set, so
+    // memory map violations name the instruction that made the access
+    fn access(next: u16, handler: impl Fn(&mut Chip8) + 'static) -> Op {
+        Box::new(move |c| {
+            c.pc = next;
+            handler(c);
+            true
+        })
+    }
+    // the handler skips by advancing the PC past the next instruction, which
+    // leaves the block
+    fn skip(next: u16, handler: impl Fn(&mut Chip8) + 'static) -> Op {
+        Box::new(move |c| {
+            c.pc = next;
+            handler(c);
+            c.pc == next
+        })
+    }
+    match opcode {
+        // the opcode policies may halt the machine or run a trap handler
+        OpCode::SYS { addr } => branch(next, move |c| c.sys(addr)),
+        OpCode::Unknown(raw) => branch(next, move |c| c.unknown(raw)),
+        OpCode::CLR => op(|c| c.clr()),
+        OpCode::RET => branch(next, |c| c.ret()),
+        OpCode::JUMP { addr } => branch(next, move |c| c.jump(addr)),
+        OpCode::CALL { addr } => branch(next, move |c| c.call(addr)),
+        OpCode::SKE { s, nn } => skip(next, move |c| c.ske(s, nn)),
+        OpCode::SKNE { s, nn } => skip(next, move |c| c.skne(s, nn)),
+        OpCode::SKRE { s, t } => skip(next, move |c| c.skre(s, t)),
+        OpCode::LOAD { s, nn } => op(move |c| c.load(s, nn)),
+        OpCode::ADD { s, nn } => op(move |c| c.add(s, nn)),
+        OpCode::MOVE { s, t } => op(move |c| c.move_reg(s, t)),
+        OpCode::OR { s, t } => op(move |c| c.or(s, t)),
+        OpCode::AND { s, t } => op(move |c| c.and(s, t)),
+        OpCode::XOR { s, t } => op(move |c| c.xor(s, t)),
+        OpCode::ADDR { s, t } => op(move |c| c.addr(s, t)),
+        OpCode::SUB { s, t } => op(move |c| c.sub(s, t)),
+        OpCode::SHR { s, t } => op(move |c| c.shr(s, t)),
+        OpCode::SUBN { s, t } => op(move |c| c.subn(s, t)),
+        OpCode::SHL { s, t } => op(move |c| c.shl(s, t)),
+        OpCode::SKRNE { s, t } => skip(next, move |c| c.skrne(s, t)),
+        OpCode::LOADI { addr } => op(move |c| c.loadi(addr)),
+        OpCode::JUMPI { addr } => branch(next, move |c| c.jumpi(addr)),
+        OpCode::RAND { s, nn } => op(move |c| c.rand(s, nn)),
+        OpCode::DRAW { s, t, n } => access(next, move |c| c.draw(s, t, n)),
+        OpCode::SKP { s } => skip(next, move |c| c.skp(s)),
+        OpCode::SKNP { s } => skip(next, move |c| c.sknp(s)),
+        OpCode::MOVED { s } => op(move |c| c.moved(s)),
+        OpCode::KEYD { s } => branch(next, move |c| c.keyd(s)),
+        OpCode::LOADD { s } => op(move |c| c.loadd(s)),
+        OpCode::LOADS { s } => op(move |c| c.loads(s)),
+        OpCode::ADDI { s } => op(move |c| c.addi(s)),
+        OpCode::LDSPR { s } => op(move |c| c.ldspr(s)),
+        OpCode::BCD { s } => access(next, move |c| c.bcd(s)),
+        OpCode::STOR { s } => access(next, move |c| c.stor(s)),
+        OpCode::READ { s } => access(next, move |c| c.read(s)),
+    }
+}
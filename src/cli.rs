@@ -0,0 +1,382 @@
+use std::ffi::OsString;
+use std::path::Path;
+
+use clap::{crate_version, value_t, App, Arg, ArgMatches, Error, ErrorKind};
+
+use super::cfg::GraphFormat;
+use super::config::Config;
+use super::font::{Font, FontSet};
+use super::movie::Movie;
+use super::palette::{Rgb, Theme};
+use super::phosphor::Persistence;
+use super::platform::Descriptor;
+use super::policy::OpcodePolicy;
+use super::quirks::QuirkPreset;
+use super::recorder::VideoFormat;
+use super::screenshot::ImageFormat;
+use super::speed::Speed;
+
+const DEFAULT_ROM_DIR: &str = "./roms";
+const DEFAULT_CONFIG: &str = "./chip_8.json"; // only read if it exists
+const DEFAULT_SCALE: &str = "10";
+pub const MAX_CYCLES_PER_FRAME: u16 = 1000; // also the limit of the speed controls
+const MAX_SCALE: u16 = 64;
+const DEFAULT_SCREENSHOT_SCALE: &str = "1";
+const DEFAULT_SCREENSHOT_FORMAT: &str = "png";
+const DEFAULT_RECORD_SCALE: &str = "1";
+const MAX_PERSISTENCE: u16 = 60;
+const DEFAULT_TURBO: &str = "unlimited";
+const DEFAULT_SYS_POLICY: &str = "warn";
+const DEFAULT_UNKNOWN_POLICY: &str = "halt";
+
+pub struct Options {
+    pub rom: Option<String>,
+    pub rom_dir: String,
+    pub cycles_per_frame: Option<u16>,
+    pub vip_timing: bool,
+    pub scale: u16,
+    pub screenshot_scale: u16,
+    pub screenshot_format: ImageFormat,
+    pub record: Option<String>,
+    pub record_scale: u16,
+    pub movie: Option<Movie>,
+    pub theme: Option<Theme>,
+    pub foreground: Option<Rgb>,
+    pub background: Option<Rgb>,
+    pub persistence: Persistence,
+    pub platform: Option<&'static Descriptor>,
+    pub quirks: Option<QuirkPreset>,
+    pub font: Option<Font>,
+    pub seed: Option<u64>,
+    pub sys_policy: OpcodePolicy,
+    pub unknown_policy: OpcodePolicy,
+    pub paused: bool,
+    pub turbo: Speed,
+    pub fullscreen: bool,
+    pub grid: bool,
+    pub analyze: bool,
+    pub graph: Option<GraphFormat>,
+}
+
+pub fn parse() -> Options {
+    parse_from(std::env::args_os()).unwrap_or_else(|e| e.exit())
+}
+
+pub fn parse_from<I, T>(args: I) -> Result<Options, Error>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let matches = App::new("chip_8")
+        .version(crate_version!())
+        .about("A simple CHIP-8 implementation in Rust")
+        .arg(
+            Arg::with_name("rom")
+                .value_name("ROM")
+                .help("Path of the ROM file to run, opens the ROM browser if omitted")
+                .index(1),
+        )
+        .arg(
+            Arg::with_name("rom-dir")
+                .short("d")
+                .long("rom-dir")
+                .value_name("DIR")
+                .help("Directory listed in the ROM browser")
+                .default_value(DEFAULT_ROM_DIR),
+        )
+        .arg(
+            Arg::with_name("cycles-per-frame")
+                .short("c")
+                .long("cycles-per-frame")
+                .value_name("N")
+                .help("Number of instructions executed per frame [default: from ROM database or 3]")
+                .validator(|value| validate_number(value, MAX_CYCLES_PER_FRAME)),
+        )
+        .arg(
+            Arg::with_name("vip-timing")
+                .long("vip-timing")
+                .help("Run as fast as a COSMAC VIP by charging every instruction its machine cycles")
+                .conflicts_with("cycles-per-frame"),
+        )
+        .arg(
+            Arg::with_name("scale")
+                .short("s")
+                .long("scale")
+                .value_name("FACTOR")
+                .help("Size of a CHIP-8 pixel in the window it starts with")
+                .default_value(DEFAULT_SCALE)
+                .validator(|value| validate_number(value, MAX_SCALE)),
+        )
+        .arg(
+            Arg::with_name("screenshot-scale")
+                .long("screenshot-scale")
+                .value_name("FACTOR")
+                .help("Size of a CHIP-8 pixel in screenshots taken with F12")
+                .default_value(DEFAULT_SCREENSHOT_SCALE)
+                .validator(|value| validate_number(value, MAX_SCALE)),
+        )
+        .arg(
+            Arg::with_name("screenshot-format")
+                .long("screenshot-format")
+                .value_name("FORMAT")
+                .help("Image format of screenshots taken with F12")
+                .possible_values(ImageFormat::NAMES)
+                .default_value(DEFAULT_SCREENSHOT_FORMAT),
+        )
+        .arg(
+            Arg::with_name("record")
+                .long("record")
+                .value_name("FILE")
+                .help("Record every frame to a .gif or .y4m file, or - for Y4M on standard output")
+                .validator(validate_video),
+        )
+        .arg(
+            Arg::with_name("record-scale")
+                .long("record-scale")
+                .value_name("FACTOR")
+                .help("Size of a CHIP-8 pixel in recordings")
+                .default_value(DEFAULT_RECORD_SCALE)
+                .validator(|value| validate_number(value, MAX_SCALE)),
+        )
+        .arg(
+            Arg::with_name("movie")
+                .long("movie")
+                .value_name("FILE")
+                .help("Play the key presses of a movie file without a window and exit at its end")
+                .requires("rom")
+                .conflicts_with_all(&["analyze", "cfg"]),
+        )
+        .arg(
+            Arg::with_name("config")
+                .long("config")
+                .value_name("FILE")
+                .help("Settings file with the colour theme [default: ./chip_8.json if it exists]"),
+        )
+        .arg(
+            Arg::with_name("theme")
+                .short("t")
+                .long("theme")
+                .value_name("THEME")
+                .help("Colour theme, switch themes with F2 [default: from ROM database or classic]")
+                .possible_values(Theme::NAMES),
+        )
+        .arg(
+            Arg::with_name("fg")
+                .long("fg")
+                .value_name("COLOR")
+                .help("Foreground colour as #RRGGBB [default: from the theme]"),
+        )
+        .arg(
+            Arg::with_name("bg")
+                .long("bg")
+                .value_name("COLOR")
+                .help("Background colour as #RRGGBB [default: from the theme]"),
+        )
+        .arg(
+            Arg::with_name("platform")
+                .short("p")
+                .long("platform")
+                .value_name("PLATFORM")
+                .help("Machine the ROM was written for, sets load address, screen size, font and quirks [default: from ROM database or chip8]")
+                .possible_values(Descriptor::NAMES),
+        )
+        .arg(
+            Arg::with_name("fade")
+                .long("fade")
+                .value_name("FRAMES")
+                .help("Let switched off pixels fade out over a number of frames to reduce flicker")
+                .validator(|value| validate_number(value, MAX_PERSISTENCE)),
+        )
+        .arg(
+            Arg::with_name("blend")
+                .long("blend")
+                .value_name("FRAMES")
+                .help("Show every pixel lit in any of the last frames to reduce flicker")
+                .validator(|value| validate_number(value, MAX_PERSISTENCE))
+                .conflicts_with("fade"),
+        )
+        .arg(
+            Arg::with_name("quirks")
+                .short("q")
+                .long("quirks")
+                .value_name("PRESET")
+                .help("Quirk preset matching the interpreter the ROM was written for [default: from ROM database or chip8]")
+                .possible_values(QuirkPreset::NAMES),
+        )
+        .arg(
+            Arg::with_name("font")
+                .long("font")
+                .value_name("FONT")
+                .help("Built-in font (vip, dream6800, eti660, chip48 or schip) or path of a font file [default: from the quirks]"),
+        )
+        .arg(
+            Arg::with_name("seed")
+                .long("seed")
+                .value_name("SEED")
+                .help("Seed for the random number generator, for reproducible runs"),
+        )
+        .arg(
+            Arg::with_name("machine-code")
+                .long("machine-code")
+                .value_name("POLICY")
+                .help("What to do on 0nnn machine code routines, which cannot be run")
+                .possible_values(OpcodePolicy::NAMES)
+                .default_value(DEFAULT_SYS_POLICY),
+        )
+        .arg(
+            Arg::with_name("unknown-opcodes")
+                .long("unknown-opcodes")
+                .value_name("POLICY")
+                .help("What to do on values that are no CHIP-8 instruction")
+                .possible_values(OpcodePolicy::NAMES)
+                .default_value(DEFAULT_UNKNOWN_POLICY),
+        )
+        .arg(
+            Arg::with_name("paused")
+                .long("paused")
+                .help("Start with emulation paused (toggle with P)"),
+        )
+        .arg(
+            Arg::with_name("turbo")
+                .long("turbo")
+                .value_name("SPEED")
+                .help("Speed while Tab is held, a factor such as 4 or unlimited")
+                .default_value(DEFAULT_TURBO)
+                .validator(|value| value.parse::<Speed>().map(|_| ())),
+        )
+        .arg(
+            Arg::with_name("fullscreen")
+                .short("f")
+                .long("fullscreen")
+                .help("Start in fullscreen mode (toggle with F11)"),
+        )
+        .arg(
+            Arg::with_name("grid")
+                .long("grid")
+                .help("Draw lines between the pixels when they are large enough (toggle with F3)"),
+        )
+        .arg(
+            Arg::with_name("analyze")
+                .long("analyze")
+                .help("Print a static analysis report of the ROM instead of running it")
+                .requires("rom"),
+        )
+        .arg(
+            Arg::with_name("cfg")
+                .long("cfg")
+                .value_name("FORMAT")
+                .help("Print the control-flow and call graph of the ROM instead of running it")
+                .possible_values(GraphFormat::NAMES)
+                .requires("rom")
+                .conflicts_with("analyze"),
+        )
+        .get_matches_from_safe(args)?;
+    let config = match parse_value(&matches, "config", load_config)? {
+        Some(config) => config,
+        None if Path::new(DEFAULT_CONFIG).exists() => load_config(DEFAULT_CONFIG)
+            .map_err(|message| Error::with_description(&message, ErrorKind::ValueValidation))?,
+        None => Config::default(),
+    };
+
+    Ok(Options {
+        rom: matches.value_of("rom").map(String::from),
+        rom_dir: matches.value_of("rom-dir").unwrap().to_string(),
+        cycles_per_frame: matches
+            .value_of("cycles-per-frame")
+            .map(|_| value_t!(matches, "cycles-per-frame", u16))
+            .transpose()?,
+        vip_timing: matches.is_present("vip-timing"),
+        scale: value_t!(matches, "scale", u16)?,
+        screenshot_scale: value_t!(matches, "screenshot-scale", u16)?,
+        screenshot_format: value_t!(matches, "screenshot-format", ImageFormat)?,
+        record: matches.value_of("record").map(String::from),
+        record_scale: value_t!(matches, "record-scale", u16)?,
+        movie: parse_value(&matches, "movie", |value| {
+            Movie::load(value).map_err(|e| format!("could not load movie {:?}: {}", value, e))
+        })?,
+        theme: matches
+            .value_of("theme")
+            .map(|_| value_t!(matches, "theme", Theme))
+            .transpose()?
+            .or(config.theme),
+        foreground: parse_value(&matches, "fg", str::parse)?.or(config.foreground),
+        background: parse_value(&matches, "bg", str::parse)?.or(config.background),
+        persistence: if matches.is_present("fade") {
+            Persistence::Fade(value_t!(matches, "fade", u8)?)
+        } else if matches.is_present("blend") {
+            Persistence::Blend(value_t!(matches, "blend", u8)?)
+        } else {
+            Persistence::Off
+        },
+        platform: matches.value_of("platform").and_then(Descriptor::find),
+        quirks: matches
+            .value_of("quirks")
+            .map(|_| value_t!(matches, "quirks", QuirkPreset))
+            .transpose()?,
+        font: parse_value(&matches, "font", parse_font)?,
+        seed: matches
+            .value_of("seed")
+            .map(|_| value_t!(matches, "seed", u64))
+            .transpose()?,
+        sys_policy: value_t!(matches, "machine-code", OpcodePolicy)?,
+        unknown_policy: value_t!(matches, "unknown-opcodes", OpcodePolicy)?,
+        paused: matches.is_present("paused"),
+        turbo: value_t!(matches, "turbo", Speed)?,
+        fullscreen: matches.is_present("fullscreen"),
+        grid: matches.is_present("grid"),
+        analyze: matches.is_present("analyze"),
+        graph: matches
+            .value_of("cfg")
+            .map(|_| value_t!(matches, "cfg", GraphFormat))
+            .transpose()?,
+    })
+}
+
+// An argument that may be missing, parsed once and reported like the
+// message of a validator.
+fn parse_value<T>(
+    matches: &ArgMatches,
+    name: &str,
+    parse: impl FnOnce(&str) -> Result<T, String>,
+) -> Result<Option<T>, Error> {
+    matches
+        .value_of(name)
+        .map(|value| {
+            parse(value).map_err(|message| {
+                Error::with_description(
+                    &format!("Invalid value for '--{}': {}", name, message),
+                    ErrorKind::ValueValidation,
+                )
+            })
+        })
+        .transpose()
+}
+
+fn load_config(value: &str) -> Result<Config, String> {
+    Config::load(value).map_err(|e| format!("could not load settings {:?}: {}", value, e))
+}
+
+// a built-in name, otherwise a file with the raw glyph data
+fn parse_font(value: &str) -> Result<Font, String> {
+    match value.parse::<FontSet>() {
+        Ok(set) => Ok(Font::builtin(set)),
+        Err(_) => Font::load(value).map_err(|e| format!("could not load font {:?}: {}", value, e)),
+    }
+}
+
+fn validate_video(value: String) -> Result<(), String> {
+    match VideoFormat::for_path(&value) {
+        Some(_) => Ok(()),
+        None => Err(format!(
+            "{:?} is not a .gif or .y4m file or - for standard output",
+            value
+        )),
+    }
+}
+
+fn validate_number(value: String, max: u16) -> Result<(), String> {
+    match value.parse::<u16>() {
+        Ok(n) if n >= 1 && n <= max => Ok(()),
+        _ => Err(format!("{:?} is not a number between 1 and {}", value, max)),
+    }
+}
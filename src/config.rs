@@ -0,0 +1,77 @@
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+use super::palette::{Rgb, Theme};
+
+// Settings kept in a JSON file, so that they need not be given on every run:
+//
+//     { "theme": "amber", "foreground": "#FFC040" }
+//
+// Every setting is optional, and those given on the command line win.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Config {
+    pub theme: Option<Theme>,
+    pub foreground: Option<Rgb>,
+    pub background: Option<Rgb>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    theme: Option<String>,
+    foreground: Option<String>,
+    background: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    Syntax(serde_json::Error),
+    Value(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Io(e) => e.fmt(f),
+            ConfigError::Syntax(e) => e.fmt(f),
+            ConfigError::Value(message) => f.write_str(message),
+        }
+    }
+}
+
+impl Error for ConfigError {}
+
+impl From<io::Error> for ConfigError {
+    fn from(e: io::Error) -> Self {
+        ConfigError::Io(e)
+    }
+}
+
+impl Config {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
+        Config::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, ConfigError> {
+        let file: ConfigFile = serde_json::from_str(text).map_err(ConfigError::Syntax)?;
+        Ok(Config {
+            theme: setting(file.theme)?,
+            foreground: setting(file.foreground)?,
+            background: setting(file.background)?,
+        })
+    }
+}
+
+fn setting<T: FromStr<Err = String>>(value: Option<String>) -> Result<Option<T>, ConfigError> {
+    value
+        .map(|value| value.parse())
+        .transpose()
+        .map_err(ConfigError::Value)
+}
@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use super::font::FontSet;
+use super::quirks::{IndexIncrement, Quirks};
+
+// the bundled files use the format of the community CHIP-8 database
+static PROGRAMS: &str = include_str!("../db/programs.json");
+static HASHES: &str = include_str!("../db/sha1-hashes.json");
+static PLATFORMS: &str = include_str!("../db/platforms.json");
+
+#[derive(Deserialize)]
+struct Program {
+    title: String,
+    release: Option<String>,
+    #[serde(default)]
+    authors: Vec<String>,
+    roms: HashMap<String, RomEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RomEntry {
+    #[serde(default)]
+    platforms: Vec<String>,
+    tickrate: Option<u16>,
+    #[serde(default)]
+    quirky_platforms: HashMap<String, QuirkSet>,
+    colors: Option<Colors>,
+    #[serde(default)]
+    keys: HashMap<String, u8>,
+}
+
+#[derive(Deserialize)]
+struct Colors {
+    #[serde(default)]
+    pixels: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Platform {
+    id: String,
+    default_tickrate: u16,
+    quirks: QuirkSet,
+}
+
+#[derive(Deserialize, Default, Copy, Clone)]
+#[serde(rename_all = "camelCase")]
+struct QuirkSet {
+    shift: Option<bool>,
+    memory_increment_by_x: Option<bool>,
+    memory_leave_i_unchanged: Option<bool>,
+    wrap: Option<bool>,
+    vblank: Option<bool>,
+    jump: Option<bool>,
+    logic: Option<bool>,
+}
+
+impl QuirkSet {
+    fn merge(self, other: QuirkSet) -> QuirkSet {
+        QuirkSet {
+            shift: other.shift.or(self.shift),
+            memory_increment_by_x: other.memory_increment_by_x.or(self.memory_increment_by_x),
+            memory_leave_i_unchanged: other
+                .memory_leave_i_unchanged
+                .or(self.memory_leave_i_unchanged),
+            wrap: other.wrap.or(self.wrap),
+            vblank: other.vblank.or(self.vblank),
+            jump: other.jump.or(self.jump),
+            logic: other.logic.or(self.logic),
+        }
+    }
+
+    fn to_quirks(self, platform: &str) -> Quirks {
+        let load_store_index = if self.memory_leave_i_unchanged.unwrap_or(false) {
+            IndexIncrement::Unchanged
+        } else if self.memory_increment_by_x.unwrap_or(false) {
+            IndexIncrement::ByX
+        } else {
+            IndexIncrement::ByXPlusOne
+        };
+        Quirks {
+            shift_uses_vy: !self.shift.unwrap_or(false),
+            load_store_index,
+            logic_resets_vf: self.logic.unwrap_or(false),
+            jump_uses_vx: self.jump.unwrap_or(false),
+            clip_sprites: !self.wrap.unwrap_or(false),
+            display_wait: self.vblank.unwrap_or(false),
+            stack_depth: stack_depth(platform),
+            stack_wraps: platform == "originalChip8" || platform == "hybridVIP",
+            font: font(platform),
+        }
+    }
+}
+
+// the database has no stack sizes or fonts, these are those of the interpreters
+fn stack_depth(platform: &str) -> Option<usize> {
+    match platform {
+        "originalChip8" | "hybridVIP" => Some(12),
+        "modernChip8" | "xochip" => None,
+        _ => Some(16),
+    }
+}
+
+fn font(platform: &str) -> FontSet {
+    match platform {
+        "originalChip8" | "hybridVIP" => FontSet::Vip,
+        "superchip1" | "superchip" | "xochip" => FontSet::Schip,
+        _ => FontSet::Chip48,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RomInfo {
+    pub title: String,
+    pub authors: Vec<String>,
+    pub release: Option<String>,
+    pub platform: Option<String>,  // id of the first platform the ROM runs on
+    pub tickrate: Option<u16>,     // instructions per frame
+    pub quirks: Option<Quirks>,
+    pub colors: Vec<String>,       // "#RRGGBB" background first, then one colour per plane
+    pub keys: HashMap<String, u8>, // key hints like "up" or "a" mapped to CHIP-8 keys
+}
+
+pub struct Database {
+    programs: Vec<Program>,
+    hashes: HashMap<String, usize>,
+    platforms: Vec<Platform>,
+}
+
+impl Database {
+    pub fn bundled() -> Self {
+        Database::parse(PROGRAMS, HASHES, PLATFORMS).expect("Bundled ROM database is invalid")
+    }
+
+    pub fn parse(programs: &str, hashes: &str, platforms: &str) -> serde_json::Result<Self> {
+        Ok(Database {
+            programs: serde_json::from_str(programs)?,
+            hashes: serde_json::from_str(hashes)?,
+            platforms: serde_json::from_str(platforms)?,
+        })
+    }
+
+    pub fn lookup(&self, sha1: &str) -> Option<RomInfo> {
+        let sha1 = sha1.to_lowercase();
+        let program = self.programs.get(*self.hashes.get(&sha1)?)?;
+        let rom = program.roms.get(&sha1)?;
+
+        let platform = rom
+            .platforms
+            .first()
+            .and_then(|id| self.platforms.iter().find(|p| &p.id == id));
+        let quirks = platform.map(|platform| {
+            let overrides = rom.quirky_platforms.get(&platform.id).copied();
+            platform
+                .quirks
+                .merge(overrides.unwrap_or_default())
+                .to_quirks(&platform.id)
+        });
+
+        Some(RomInfo {
+            title: program.title.clone(),
+            authors: program.authors.clone(),
+            release: program.release.clone(),
+            platform: rom.platforms.first().cloned(),
+            tickrate: rom.tickrate.or_else(|| platform.map(|p| p.default_tickrate)),
+            quirks,
+            colors: rom
+                .colors
+                .as_ref()
+                .map(|colors| colors.pixels.clone())
+                .unwrap_or_default(),
+            keys: rom.keys.clone(),
+        })
+    }
+}
@@ -0,0 +1,35 @@
+use std::error::Error;
+use std::fmt;
+
+// Conditions a program can run into that have no defined behaviour. The
+// machine halts on them instead of guessing; addresses are those of the
+// instruction that faulted.
+#[rustfmt::skip]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Fault {
+    InvalidOpcode { addr: u16, raw: u16 },
+    MachineCode { addr: u16, target: u16 }, // 0nnn, routines of the host CPU cannot be run
+    StackOverflow { addr: u16 },            // CALL with all stack entries in use
+    StackUnderflow { addr: u16 },           // RET outside of any subroutine
+}
+
+impl fmt::Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Fault::InvalidOpcode { addr, raw } => {
+                write!(f, "0x{:03X}: invalid opcode 0x{:04X}", addr, raw)
+            }
+            Fault::MachineCode { addr, target } => {
+                write!(
+                    f,
+                    "0x{:03X}: machine code routine at 0x{:03X}",
+                    addr, target
+                )
+            }
+            Fault::StackOverflow { addr } => write!(f, "0x{:03X}: stack overflow", addr),
+            Fault::StackUnderflow { addr } => write!(f, "0x{:03X}: stack underflow", addr),
+        }
+    }
+}
+
+impl Error for Fault {}
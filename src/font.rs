@@ -0,0 +1,252 @@
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+use std::str::FromStr;
+
+const GLYPHS: usize = 16;
+const SMALL_GLYPH_HEIGHT: usize = 5;
+const BIG_GLYPH_HEIGHT: usize = 10;
+const SMALL_SIZE: usize = GLYPHS * SMALL_GLYPH_HEIGHT;
+
+#[rustfmt::skip]
+static VIP: [u8; SMALL_SIZE] = [
+  0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
+  0x60, 0x20, 0x20, 0x20, 0x70, // 1
+  0xF0, 0x10, 0xF0, 0x80, 0xF0, // 2
+  0xF0, 0x10, 0x70, 0x10, 0xF0, // 3
+  0xA0, 0xA0, 0xF0, 0x20, 0x20, // 4
+  0xF0, 0x80, 0xF0, 0x10, 0xF0, // 5
+  0xF0, 0x80, 0xF0, 0x90, 0xF0, // 6
+  0xF0, 0x10, 0x10, 0x10, 0x10, // 7
+  0xF0, 0x90, 0xF0, 0x90, 0xF0, // 8
+  0xF0, 0x90, 0xF0, 0x10, 0xF0, // 9
+  0xF0, 0x90, 0xF0, 0x90, 0x90, // A
+  0xF0, 0x50, 0x70, 0x50, 0xF0, // B
+  0xF0, 0x80, 0x80, 0x80, 0xF0, // C
+  0xF0, 0x50, 0x50, 0x50, 0xF0, // D
+  0xF0, 0x80, 0xF0, 0x80, 0xF0, // E
+  0xF0, 0x80, 0xF0, 0x80, 0x80  // F
+];
+
+#[rustfmt::skip]
+static DREAM_6800: [u8; SMALL_SIZE] = [
+  0xE0, 0xA0, 0xA0, 0xA0, 0xE0, // 0
+  0x40, 0x40, 0x40, 0x40, 0x40, // 1
+  0xE0, 0x20, 0xE0, 0x80, 0xE0, // 2
+  0xE0, 0x20, 0xE0, 0x20, 0xE0, // 3
+  0x80, 0xA0, 0xA0, 0xE0, 0x20, // 4
+  0xE0, 0x80, 0xE0, 0x20, 0xE0, // 5
+  0xE0, 0x80, 0xE0, 0xA0, 0xE0, // 6
+  0xE0, 0x20, 0x20, 0x20, 0x20, // 7
+  0xE0, 0xA0, 0xE0, 0xA0, 0xE0, // 8
+  0xE0, 0xA0, 0xE0, 0x20, 0xE0, // 9
+  0xE0, 0xA0, 0xE0, 0xA0, 0xA0, // A
+  0xC0, 0xA0, 0xE0, 0xA0, 0xC0, // B
+  0xE0, 0x80, 0x80, 0x80, 0xE0, // C
+  0xC0, 0xA0, 0xA0, 0xA0, 0xC0, // D
+  0xE0, 0x80, 0xE0, 0x80, 0xE0, // E
+  0xE0, 0x80, 0xC0, 0x80, 0x80  // F
+];
+
+#[rustfmt::skip]
+static ETI_660: [u8; SMALL_SIZE] = [
+  0xE0, 0xA0, 0xA0, 0xA0, 0xE0, // 0
+  0x20, 0x20, 0x20, 0x20, 0x20, // 1
+  0xE0, 0x20, 0xE0, 0x80, 0xE0, // 2
+  0xE0, 0x20, 0xE0, 0x20, 0xE0, // 3
+  0xA0, 0xA0, 0xE0, 0x20, 0x20, // 4
+  0xE0, 0x80, 0xE0, 0x20, 0xE0, // 5
+  0xE0, 0x80, 0xE0, 0xA0, 0xE0, // 6
+  0xE0, 0x20, 0x20, 0x20, 0x20, // 7
+  0xE0, 0xA0, 0xE0, 0xA0, 0xE0, // 8
+  0xE0, 0xA0, 0xE0, 0x20, 0xE0, // 9
+  0xE0, 0xA0, 0xE0, 0xA0, 0xA0, // A
+  0x80, 0x80, 0xE0, 0xA0, 0xE0, // B
+  0xE0, 0x80, 0x80, 0x80, 0xE0, // C
+  0x20, 0x20, 0xE0, 0xA0, 0xE0, // D
+  0xE0, 0x80, 0xE0, 0x80, 0xE0, // E
+  0xE0, 0x80, 0xC0, 0x80, 0x80  // F
+];
+
+// also the small font of SUPER-CHIP, and the one this emulator always used
+#[rustfmt::skip]
+static CHIP_48: [u8; SMALL_SIZE] = [
+  0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
+  0x20, 0x60, 0x20, 0x20, 0x70, // 1
+  0xF0, 0x10, 0xF0, 0x80, 0xF0, // 2
+  0xF0, 0x10, 0xF0, 0x10, 0xF0, // 3
+  0x90, 0x90, 0xF0, 0x10, 0x10, // 4
+  0xF0, 0x80, 0xF0, 0x10, 0xF0, // 5
+  0xF0, 0x80, 0xF0, 0x90, 0xF0, // 6
+  0xF0, 0x10, 0x20, 0x40, 0x40, // 7
+  0xF0, 0x90, 0xF0, 0x90, 0xF0, // 8
+  0xF0, 0x90, 0xF0, 0x10, 0xF0, // 9
+  0xF0, 0x90, 0xF0, 0x90, 0x90, // A
+  0xE0, 0x90, 0xE0, 0x90, 0xE0, // B
+  0xF0, 0x80, 0x80, 0x80, 0xF0, // C
+  0xE0, 0x90, 0x90, 0x90, 0xE0, // D
+  0xF0, 0x80, 0xF0, 0x80, 0xF0, // E
+  0xF0, 0x80, 0xF0, 0x80, 0x80  // F
+];
+
+// SUPER-CHIP 1.1 only has big digits, no letters
+#[rustfmt::skip]
+static SCHIP_BIG: [u8; 10 * BIG_GLYPH_HEIGHT] = [
+  0xFF, 0xFF, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, // 0
+  0x18, 0x78, 0x78, 0x18, 0x18, 0x18, 0x18, 0x18, 0xFF, 0xFF, // 1
+  0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // 2
+  0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 3
+  0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0x03, 0x03, // 4
+  0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 5
+  0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // 6
+  0xFF, 0xFF, 0x03, 0x03, 0x06, 0x0C, 0x18, 0x18, 0x18, 0x18, // 7
+  0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // 8
+  0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF  // 9
+];
+
+// The built-in fonts, named after the interpreters they come from.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FontSet {
+    Vip,
+    Dream6800,
+    Eti660,
+    Chip48,
+    Schip, // the CHIP-48 font plus the big digits
+}
+
+impl FontSet {
+    pub const NAMES: &'static [&'static str] = &["vip", "dream6800", "eti660", "chip48", "schip"];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            FontSet::Vip => "vip",
+            FontSet::Dream6800 => "dream6800",
+            FontSet::Eti660 => "eti660",
+            FontSet::Chip48 => "chip48",
+            FontSet::Schip => "schip",
+        }
+    }
+}
+
+impl FromStr for FontSet {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "vip" => Ok(FontSet::Vip),
+            "dream6800" => Ok(FontSet::Dream6800),
+            "eti660" => Ok(FontSet::Eti660),
+            "chip48" => Ok(FontSet::Chip48),
+            "schip" => Ok(FontSet::Schip),
+            _ => Err(format!(
+                "unknown font {:?}, expected one of {}",
+                s,
+                FontSet::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+impl fmt::Display for FontSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[derive(Debug)]
+pub enum FontError {
+    Io(io::Error),
+    Size(usize),
+}
+
+impl fmt::Display for FontError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FontError::Io(e) => e.fmt(f),
+            FontError::Size(size) => write!(
+                f,
+                "font is {} bytes, expected {} for the small font, optionally followed by 10 or 16 big glyphs of {} bytes",
+                size, SMALL_SIZE, BIG_GLYPH_HEIGHT
+            ),
+        }
+    }
+}
+
+impl Error for FontError {}
+
+impl From<io::Error> for FontError {
+    fn from(e: io::Error) -> Self {
+        FontError::Io(e)
+    }
+}
+
+// The hex digits LD F, Vx points the index register to: 16 glyphs 5 lines
+// high, and optionally big digits 10 lines high.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Font {
+    small: Vec<u8>,
+    big: Option<Vec<u8>>,
+}
+
+impl Font {
+    pub fn builtin(set: FontSet) -> Self {
+        let small = match set {
+            FontSet::Vip => &VIP,
+            FontSet::Dream6800 => &DREAM_6800,
+            FontSet::Eti660 => &ETI_660,
+            FontSet::Chip48 | FontSet::Schip => &CHIP_48,
+        };
+        Font {
+            small: small.to_vec(),
+            big: match set {
+                FontSet::Schip => Some(SCHIP_BIG.to_vec()),
+                _ => None,
+            },
+        }
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, FontError> {
+        let mut file = File::open(path)?;
+        let mut buf = Vec::new();
+        file.read_to_end(&mut buf)?;
+        Font::from_bytes(&buf)
+    }
+
+    // the small glyphs, directly followed by the big ones if there are any
+    pub fn from_bytes(data: &[u8]) -> Result<Self, FontError> {
+        if data.len() < SMALL_SIZE {
+            return Err(FontError::Size(data.len()));
+        }
+        let (small, big) = data.split_at(SMALL_SIZE);
+        let big = match big.len() / BIG_GLYPH_HEIGHT {
+            _ if big.is_empty() => None,
+            10 | GLYPHS if big.len() % BIG_GLYPH_HEIGHT == 0 => Some(big.to_vec()),
+            _ => return Err(FontError::Size(data.len())),
+        };
+        Ok(Font {
+            small: small.to_vec(),
+            big,
+        })
+    }
+
+    pub fn small(&self) -> &[u8] {
+        &self.small
+    }
+
+    pub fn big(&self) -> Option<&[u8]> {
+        self.big.as_deref()
+    }
+
+    // offset of the small glyph for a hex digit from the start of the font
+    pub fn glyph(&self, digit: u8) -> u16 {
+        ((digit & 0xF) as usize * SMALL_GLYPH_HEIGHT) as u16
+    }
+}
+
+impl Default for Font {
+    fn default() -> Self {
+        Font::builtin(FontSet::Chip48)
+    }
+}
@@ -11,16 +11,21 @@ impl Keys {
     }
 
     pub fn set(&mut self, index: u8) {
-        self.keys |= 1 << index;
+        self.keys |= bit(index);
     }
 
     pub fn unset(&mut self, index: u8) {
-        self.keys &= 0 << index;
+        self.keys &= !bit(index);
     }
 
     pub fn get(&self, index: u8) -> bool {
-        let bit = 1 << index;
+        let bit = bit(index);
         (self.keys & bit) == bit
     }
 
 }
+
+// there are only 16 keys, higher indices wrap around
+fn bit(index: u8) -> u16 {
+    1 << (index & 0xF)
+}
@@ -0,0 +1,29 @@
+pub mod analysis;
+pub mod browser;
+pub mod cfg;
+pub mod chip8;
+pub mod cli;
+pub mod config;
+pub mod database;
+pub mod fault;
+pub mod font;
+mod keys;
+mod mem;
+pub mod memory_map;
+pub mod movie;
+pub mod octo;
+pub mod opcode;
+pub mod palette;
+pub mod phosphor;
+pub mod platform;
+pub mod policy;
+pub mod quirks;
+pub mod recorder;
+pub mod rom;
+pub mod screen_buffer;
+pub mod screenshot;
+pub mod speed;
+pub mod stack;
+pub mod state;
+pub mod timing;
+pub mod viewport;
@@ -1,117 +1,706 @@
-mod chip8;
-mod keys;
-mod mem;
-mod opcode;
-mod rom;
-mod screen_buffer;
+mod menu;
 
-use chip8::Chip8;
-use rom::Rom;
+use chip_8::browser::RomBrowser;
+use chip_8::chip8::Chip8;
+use chip_8::cli::{self, Options, MAX_CYCLES_PER_FRAME};
+use chip_8::database::{Database, RomInfo};
+use chip_8::movie::Movie;
+use chip_8::octo::OctoOptions;
+use chip_8::palette::{Palette, Rgb, Theme};
+use chip_8::phosphor::{Phosphor, MAX_INTENSITY};
+use chip_8::platform::{Descriptor, DESCRIPTORS};
+use chip_8::quirks::Quirks;
+use chip_8::recorder::Recorder;
+use chip_8::rom::{Rom, RomError};
+use chip_8::screenshot;
+use chip_8::speed::{Pace, RateMeter, SpeedControl};
+use chip_8::timing::Timing;
+use chip_8::viewport::Viewport;
+use chip_8::{analysis, cfg};
 
-use ggez::conf::WindowSetup;
+use ggez::conf::{FullscreenType, WindowMode, WindowSetup};
 use ggez::event::{self, EventHandler};
 use ggez::event::{KeyCode, KeyMods};
-use ggez::graphics;
+use ggez::graphics::{
+    self, Color, DrawMode, DrawParam, FilterMode, MeshBuilder, Rect, Scale, Text, TextFragment,
+};
 use ggez::nalgebra::Point2;
-use ggez::{Context, ContextBuilder, GameResult};
+use ggez::{timer, Context, ContextBuilder, GameError, GameResult};
 
-const CYCLES_PER_FRAME: u8 = 3;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process;
+use std::time::{Duration, Instant};
+
+const DEFAULT_CYCLES_PER_FRAME: u16 = 3;
+const FRAMES_PER_SECOND: u32 = 60;
+const MIN_GRID_SCALE: u32 = 4; // below that the grid would hide too much of the picture
+const TURBO_BUDGET: Duration = Duration::from_millis(12); // of the 16.7 ms of a frame
+const OSD_TEXT_SIZE: f32 = 16.0;
 
 fn main() -> GameResult {
+    let options = cli::parse();
+    let rom = options.rom.as_ref().map(|path| {
+        Rom::load(path).unwrap_or_else(|e| {
+            eprintln!("error: Could not open ROM file at {:?}: {}", path, e);
+            process::exit(1);
+        })
+    });
+    let database = Database::bundled();
+    // the platform the ROM will be started with, see MainWindow::start
+    let info = rom.as_ref().and_then(|rom| database.lookup(&rom.sha1()));
+    let platform = detect_platform(&options, info.as_ref());
+    if options.analyze {
+        // clap makes sure a ROM was given
+        print!("{}", analysis::analyze(rom.unwrap().data(), platform));
+        return Ok(());
+    }
+    if let Some(format) = options.graph {
+        print!("{}", cfg::build(rom.unwrap().data()).export(format));
+        return Ok(());
+    }
+    if let Some(Err(e)) = rom.as_ref().map(|rom| rom.validate_for(platform)) {
+        eprintln!("error: Could not load ROM file at {:?}: {}", options.rom.unwrap(), e);
+        process::exit(1);
+    }
+    if let Some(movie) = &options.movie {
+        // clap makes sure a ROM was given
+        if let Err(e) = play_movie(&options, &database, movie, rom.unwrap()) {
+            eprintln!("error: {}", e);
+            process::exit(1);
+        }
+        return Ok(());
+    }
+    let menu = match rom {
+        Some(_) => None,
+        None => Some(RomBrowser::scan(&options.rom_dir, &database).unwrap_or_else(|e| {
+            eprintln!("error: Could not open ROM directory {:?}: {}", options.rom_dir, e);
+            process::exit(1);
+        })),
+    };
+
     let window_setup = WindowSetup::default().title("CHIP-8");
+    let fullscreen_type = if options.fullscreen {
+        FullscreenType::Desktop
+    } else {
+        FullscreenType::Windowed
+    };
+    let window_mode = WindowMode::default()
+        .dimensions(
+            (platform.screen_width * options.scale) as f32,
+            (platform.screen_height * options.scale) as f32,
+        )
+        .min_dimensions(platform.screen_width as f32, platform.screen_height as f32)
+        .resizable(true)
+        .fullscreen_type(fullscreen_type);
     let (mut ctx, mut event_loop) = ContextBuilder::new("chip_8", "demilich")
         .window_setup(window_setup)
+        .window_mode(window_mode)
         .build()
         .expect("Could not create ggez context!");
 
-    let mut window = MainWindow::new(&mut ctx)?;
+    let mut window = MainWindow::new(&mut ctx, options, database, rom, menu)?;
 
     match event::run(&mut ctx, &mut event_loop, &mut window) {
-        Ok(_) => println!("Exited cleanly."),
-        Err(e) => println!("Error occured: {}", e),
+        Ok(_) => eprintln!("Exited cleanly."),
+        Err(e) => eprintln!("Error occured: {}", e),
+    }
+    window.stop_recording();
+    Ok(())
+}
+
+// Runs the ROM through a movie without a window, recording it if asked to.
+fn play_movie(
+    options: &Options,
+    database: &Database,
+    movie: &Movie,
+    rom: Rom,
+) -> Result<(), String> {
+    let info = database.lookup(&rom.sha1());
+    let platform = detect_platform(options, info.as_ref());
+    rom.validate_for(platform).map_err(|e| e.to_string())?;
+    let mut chip8 = Chip8::new();
+    // a movie only replays the same with the same random numbers
+    chip8.seed(options.seed.unwrap_or(0));
+    chip8.set_sys_policy(options.sys_policy);
+    chip8.set_unknown_policy(options.unknown_policy);
+    configure(&mut chip8, options, platform, info.as_ref(), rom.options());
+    chip8.init();
+    chip8.load_rom(rom).map_err(|e| e.to_string())?;
+
+    let mut recorder = match &options.record {
+        Some(path) => {
+            let screen = chip8.screen_buffer();
+            let palette = palette(options, info.as_ref(), None);
+            let recorder = Recorder::create(
+                path,
+                screen.width(),
+                screen.height(),
+                palette,
+                options.record_scale,
+            );
+            Some(recorder.map_err(|e| format!("Could not record to {:?}: {}", path, e))?)
+        }
+        None => None,
+    };
+    movie
+        .play(&mut chip8, |chip8| match &mut recorder {
+            Some(recorder) => recorder.frame(chip8.screen_buffer()),
+            None => Ok(()),
+        })
+        .map_err(|e| format!("Could not record: {}", e))?;
+    if let Some(recorder) = recorder {
+        recorder
+            .finish()
+            .map_err(|e| format!("Could not record: {}", e))?;
+    }
+    if let Some(fault) = chip8.fault() {
+        eprintln!("Halted: {}", fault);
     }
     Ok(())
 }
 
+// the platform given on the command line, otherwise the one the ROM database
+// knows for the ROM
+fn detect_platform(options: &Options, info: Option<&RomInfo>) -> &'static Descriptor {
+    let detected = info
+        .and_then(|info| info.platform.as_deref())
+        .and_then(Descriptor::for_database);
+    options.platform.or(detected).unwrap_or(&DESCRIPTORS[0])
+}
+
+// Sets up the machine for a ROM and returns the instructions per frame.
+// Explicit command-line options win over the options embedded in an Octo
+// cartridge, which in turn win over the settings found in the ROM database.
+fn configure(
+    chip8: &mut Chip8,
+    options: &Options,
+    platform: &Descriptor,
+    info: Option<&RomInfo>,
+    octo: Option<&OctoOptions>,
+) -> u16 {
+    let cycles_per_frame = options
+        .cycles_per_frame
+        .or_else(|| octo.and_then(|octo| octo.tickrate))
+        .or_else(|| info.and_then(|info| info.tickrate))
+        .unwrap_or(DEFAULT_CYCLES_PER_FRAME);
+    chip8.set_timing(if options.vip_timing {
+        Timing::Vip
+    } else {
+        Timing::Fixed(cycles_per_frame as u32)
+    });
+
+    chip8.set_platform(platform);
+    let quirks = match (options.quirks, octo) {
+        (Some(preset), _) => Quirks::from_preset(preset),
+        (None, Some(octo)) => octo.quirks(),
+        // a platform picked by hand brings its own quirks
+        (None, None) if options.platform.is_some() => platform.quirks(),
+        (None, None) => info
+            .and_then(|info| info.quirks)
+            .unwrap_or_else(|| platform.quirks()),
+    };
+    chip8.set_quirks(quirks);
+    if let Some(font) = &options.font {
+        chip8.set_font(font.clone());
+    }
+    cycles_per_frame
+}
+
+// a theme chosen on the command line or in the settings file replaces the
+// colours of the ROM
+fn palette(options: &Options, info: Option<&RomInfo>, octo: Option<&OctoOptions>) -> Palette {
+    let colors = match octo {
+        Some(octo) => octo.colors(),
+        None => info.map(|info| info.colors.clone()).unwrap_or_default(),
+    };
+    let palette = match options.theme {
+        Some(theme) => theme.palette(),
+        None => Palette::default().with_colors(&colors),
+    };
+    with_color_overrides(palette, options)
+}
+
+fn with_color_overrides(mut palette: Palette, options: &Options) -> Palette {
+    if let Some(background) = options.background {
+        palette.colors[0] = background;
+    }
+    if let Some(foreground) = options.foreground {
+        palette.colors[1] = foreground;
+    }
+    palette
+}
+
+fn color(Rgb(r, g, b): Rgb) -> Color {
+    Color::from_rgb(r, g, b)
+}
+
 struct MainWindow {
     redraw: bool,
+    speed: SpeedControl,
+    meter: RateMeter,
+    osd: bool, // shows speed and rates on top of the screen
     chip8: Chip8,
-    width: u16,
-    height: u16,
-    scale: u16,
-    buffer: Vec<u8>,
+    options: Options,
+    database: Database,
+    rom_dir: PathBuf,
+    rom_path: Option<PathBuf>,
+    menu: Option<RomBrowser>,
+    cycles_per_frame: u16,
+    fullscreen: bool,
+    grid: bool,
+    theme: Theme,
+    palette: Palette,
+    key_hints: HashMap<String, u8>,
+    phosphor: Phosphor,
+    screen_changed: bool, // without a frame running, by loading a state or ROM
+    recorder: Option<Recorder<Box<dyn Write>>>,
+    buffer: Vec<u8>, // the screen at its own resolution, RGBA
 }
 
 impl MainWindow {
-    pub fn new(_ctx: &mut Context) -> GameResult<MainWindow> {
-        let scale = 10;
-        let width = chip8::SCREEN_WIDTH * scale;
-        let height = chip8::SCREEN_HEIGHT * scale;
-        let rom = Rom::load("./roms/PONG2");
+    pub fn new(
+        ctx: &mut Context,
+        options: Options,
+        database: Database,
+        rom: Option<Rom>,
+        menu: Option<RomBrowser>,
+    ) -> GameResult<MainWindow> {
+        let persistence = options.persistence;
+        let (fullscreen, grid) = (options.fullscreen, options.grid);
+        let mut speed = SpeedControl::new(options.turbo);
+        speed.set_paused(options.paused);
+        let platform = options.platform.cloned().unwrap_or_default();
 
         let mut chip8 = Chip8::new();
-        chip8.init();
-        chip8.load_rom(rom);
+        if let Some(seed) = options.seed {
+            chip8.seed(seed);
+        }
+        chip8.set_sys_policy(options.sys_policy);
+        chip8.set_unknown_policy(options.unknown_policy);
+        let record = options.record.clone();
 
-        let state = MainWindow {
+        let mut state = MainWindow {
             redraw: true,
+            speed,
+            meter: RateMeter::new(),
+            osd: false,
             chip8,
-            width,
-            height,
-            scale,
-            buffer: vec![0; width as usize * height as usize * 4],
+            rom_dir: PathBuf::from(&options.rom_dir),
+            rom_path: options.rom.as_ref().map(PathBuf::from),
+            options,
+            database,
+            menu,
+            cycles_per_frame: DEFAULT_CYCLES_PER_FRAME,
+            fullscreen,
+            grid,
+            theme: Theme::Classic,
+            palette: Palette::default(),
+            key_hints: HashMap::new(),
+            phosphor: Phosphor::new(persistence),
+            screen_changed: false,
+            recorder: None,
+            buffer: Vec::new(),
         };
+        state.apply_settings(&platform, None, None);
+        state.fit_screen_coordinates(ctx)?;
+        if let Some(rom) = rom {
+            state
+                .start(ctx, rom)
+                .map_err(|e| GameError::ResourceLoadError(e.to_string()))?;
+        }
+        if let Some(path) = record {
+            state.start_recording(&path);
+        }
         Ok(state)
     }
-}
 
-impl EventHandler for MainWindow {
-    fn update(&mut self, _ctx: &mut Context) -> GameResult<()> {
-        for _ in 0..CYCLES_PER_FRAME {
-            self.chip8.run_cycle();
-            if self.chip8.redraw() {
+    fn start(&mut self, ctx: &mut Context, rom: Rom) -> Result<(), RomError> {
+        // checked up front so a failed start leaves the running game alone
+        let info = self.database.lookup(&rom.sha1());
+        let platform = detect_platform(&self.options, info.as_ref());
+        rom.validate_for(platform)?;
+        self.apply_settings(platform, info.as_ref(), rom.options());
+        if let Some(info) = &info {
+            graphics::set_window_title(ctx, &format!("CHIP-8 - {}", info.title));
+        }
+
+        self.chip8.reset();
+        self.chip8.init();
+        self.chip8.load_rom(rom)?;
+        self.screen_changed = true;
+        self.redraw = true;
+        Ok(())
+    }
+
+    fn apply_settings(
+        &mut self,
+        platform: &Descriptor,
+        info: Option<&RomInfo>,
+        octo: Option<&OctoOptions>,
+    ) {
+        self.cycles_per_frame = configure(&mut self.chip8, &self.options, platform, info, octo);
+        self.theme = self.options.theme.unwrap_or(Theme::Classic);
+        self.palette = palette(&self.options, info, octo);
+        self.key_hints = info.map(|info| info.keys.clone()).unwrap_or_default();
+    }
+
+    fn switch_theme(&mut self) {
+        self.theme = self.theme.next();
+        self.palette = with_color_overrides(self.theme.palette(), &self.options);
+        eprintln!("Theme: {}", self.theme);
+        self.redraw = true;
+    }
+
+    fn start_recording(&mut self, path: &str) {
+        let screen = self.chip8.screen_buffer();
+        let palette = self.palette;
+        let scale = self.options.record_scale;
+        match Recorder::create(path, screen.width(), screen.height(), palette, scale) {
+            Ok(recorder) => {
+                eprintln!("Recording to {:?}", path);
+                self.recorder = Some(recorder);
+            }
+            Err(e) => eprintln!("Could not record to {:?}: {}", path, e),
+        }
+    }
+
+    fn stop_recording(&mut self) {
+        if let Some(recorder) = self.recorder.take() {
+            let frames = recorder.frames();
+            match recorder.finish() {
+                Ok(()) => eprintln!("Recorded {} frames", frames),
+                Err(e) => eprintln!("Could not finish the recording: {}", e),
+            }
+        }
+    }
+
+    // F10 records to the working directory, named like the screenshots
+    fn toggle_recording(&mut self) {
+        if self.recorder.is_some() {
+            return self.stop_recording();
+        }
+        let path = screenshot::file_name(&self.rom_name(), self.chip8.frame(), "gif");
+        self.start_recording(&path);
+    }
+
+    fn record_frame(&mut self) {
+        if let Some(recorder) = &mut self.recorder {
+            if let Err(e) = recorder.frame(self.chip8.screen_buffer()) {
+                eprintln!("Recording stopped: {}", e);
+                self.stop_recording();
+            }
+        }
+    }
+
+    // one unit per pixel of the window, so that the screen can be scaled by
+    // whole pixels
+    fn fit_screen_coordinates(&mut self, ctx: &mut Context) -> GameResult {
+        let (width, height) = graphics::drawable_size(ctx);
+        self.redraw = true;
+        graphics::set_screen_coordinates(ctx, Rect::new(0.0, 0.0, width, height))
+    }
+
+    fn toggle_fullscreen(&mut self, ctx: &mut Context) {
+        self.fullscreen = !self.fullscreen;
+        let fullscreen_type = if self.fullscreen {
+            FullscreenType::Desktop
+        } else {
+            FullscreenType::Windowed
+        };
+        if let Err(e) = graphics::set_fullscreen(ctx, fullscreen_type)
+            .and_then(|_| self.fit_screen_coordinates(ctx))
+        {
+            eprintln!("Could not switch to fullscreen: {}", e);
+        }
+    }
+
+    fn open_menu(&mut self) {
+        match RomBrowser::scan(&self.rom_dir, &self.database) {
+            Ok(mut menu) => {
+                if let Some(path) = &self.rom_path {
+                    menu.select_path(path);
+                }
+                self.menu = Some(menu);
+            }
+            Err(e) => eprintln!("Could not open ROM directory {:?}: {}", self.rom_dir, e),
+        }
+    }
+
+    fn start_selected(&mut self, ctx: &mut Context) {
+        let path = match self.menu.as_ref().and_then(|menu| menu.selected()) {
+            Some(entry) => entry.path.clone(),
+            None => return,
+        };
+        match Rom::load(&path).and_then(|rom| self.start(ctx, rom)) {
+            Ok(()) => {
+                self.rom_path = Some(path);
+                self.menu = None;
+            }
+            Err(e) => {
+                if let Some(menu) = self.menu.as_mut() {
+                    menu.set_message(format!("Could not open {}: {}", path.display(), e));
+                }
+            }
+        }
+    }
+
+    fn menu_key_down(&mut self, ctx: &mut Context, key: KeyCode) {
+        if let Some(menu) = self.menu.as_mut() {
+            menu.clear_message();
+            match key {
+                KeyCode::Up => menu.select_previous(),
+                KeyCode::Down => menu.select_next(),
+                KeyCode::Home => menu.select_first(),
+                KeyCode::End => menu.select_last(),
+                KeyCode::Return => self.start_selected(ctx),
+                _ => (),
+            }
+        }
+    }
+
+    // save states are kept next to the ROM they belong to
+    fn state_path(&self) -> Option<PathBuf> {
+        self.rom_path.as_ref().map(|path| {
+            let mut name = path.as_os_str().to_owned();
+            name.push(".state");
+            PathBuf::from(name)
+        })
+    }
+
+    fn save_state(&self) {
+        if let Some(path) = self.state_path() {
+            match fs::write(&path, self.chip8.save_state()) {
+                Ok(()) => eprintln!("Saved state to {:?}", path),
+                Err(e) => eprintln!("Could not save state to {:?}: {}", path, e),
+            }
+        }
+    }
+
+    fn load_state(&mut self) {
+        if let Some(path) = self.state_path() {
+            let loaded = fs::read(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|data| self.chip8.load_state(&data).map_err(|e| e.to_string()));
+            match loaded {
+                Ok(()) => {
+                    self.screen_changed = true;
+                    self.redraw = true;
+                }
+                Err(e) => eprintln!("Could not load state from {:?}: {}", path, e),
+            }
+        }
+    }
+
+    fn rom_name(&self) -> String {
+        self.rom_path
+            .as_ref()
+            .and_then(|path| path.file_stem())
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "chip8".to_string())
+    }
+
+    // written to the working directory, named after the ROM and the frame
+    fn screenshot(&self) {
+        let format = self.options.screenshot_format;
+        let path = screenshot::file_name(&self.rom_name(), self.chip8.frame(), format.extension());
+        let screen = self.chip8.screen_buffer();
+        let scale = self.options.screenshot_scale;
+        match screenshot::save(&path, screen, &self.palette, scale, format) {
+            Ok(()) => eprintln!("Saved screenshot to {:?}", path),
+            Err(e) => eprintln!("Could not save screenshot to {:?}: {}", path, e),
+        }
+    }
+
+    // Runs frames while keep_going allows it, given the frames run so far,
+    // and returns how many frames and instructions ran.
+    fn run_frames(&mut self, keep_going: impl Fn(u32) -> bool) -> (u32, u32) {
+        let (mut frames, mut instructions) = (0, 0);
+        while keep_going(frames) && self.chip8.fault().is_none() {
+            instructions += self.chip8.run_frame();
+            frames += 1;
+            self.record_frame();
+            // fading pixels change without the screen changing
+            if self.phosphor.update(self.chip8.screen_buffer()) {
                 self.redraw = true;
             }
         }
+        (frames, instructions)
+    }
+
+    fn change_cycles_per_frame(&mut self, change: i32) {
+        if self.chip8.timing() == Timing::Vip {
+            eprintln!("Instructions per frame follow the VIP timing, see --vip-timing");
+            return;
+        }
+        let cycles = (self.cycles_per_frame as i32 + change).clamp(1, MAX_CYCLES_PER_FRAME as i32);
+        self.cycles_per_frame = cycles as u16;
+        self.chip8.set_timing(Timing::Fixed(cycles as u32));
+        eprintln!("Instructions per frame: {}", cycles);
+    }
+
+    fn osd_text(&self) -> String {
+        let cycles = match self.chip8.timing() {
+            Timing::Vip => "VIP".to_string(),
+            Timing::Fixed(cycles) => cycles.to_string(),
+        };
+        let state = match self.speed.paused() {
+            true => "paused".to_string(),
+            false => self.speed.speed().to_string(),
+        };
+        format!(
+            "{} FPS  {} IPS  {} IPF  {}",
+            self.meter.fps(),
+            self.meter.ips(),
+            cycles,
+            state
+        )
+    }
+
+    // arrow keys and space/shift are mapped to the keys the ROM database suggests
+    fn hinted_key(&self, key: KeyCode) -> Option<u8> {
+        let hint = match key {
+            KeyCode::Up => "up",
+            KeyCode::Down => "down",
+            KeyCode::Left => "left",
+            KeyCode::Right => "right",
+            KeyCode::Space => "a",
+            KeyCode::LShift => "b",
+            _ => return None,
+        };
+        self.key_hints.get(hint).copied()
+    }
+}
+
+impl EventHandler for MainWindow {
+    fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
+        if self.menu.is_some() {
+            return Ok(());
+        }
+
+        let running = self.chip8.fault().is_none();
+        let warned = self.chip8.warnings() > 0;
+        while timer::check_update_time(ctx, FRAMES_PER_SECOND) {
+            let (frames, instructions) = match self.speed.tick() {
+                Pace::Frames(count) => self.run_frames(|frames| frames < count),
+                Pace::Unlimited => {
+                    let start = Instant::now();
+                    self.run_frames(|_| start.elapsed() < TURBO_BUDGET)
+                }
+            };
+            self.meter.record(frames, instructions);
+        }
+        if self.osd {
+            self.redraw = true;
+        }
+        if !warned && self.chip8.warnings() > 0 {
+            eprintln!(
+                "Skipping opcodes that cannot be run, see --machine-code and --unknown-opcodes"
+            );
+        }
+        if let (true, Some(fault)) = (running, self.chip8.fault()) {
+            eprintln!("Halted: {}", fault);
+            graphics::set_window_title(ctx, &format!("CHIP-8 - halted: {}", fault));
+        }
 
         Ok(())
     }
 
     fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        if let Some(menu) = &self.menu {
+            return menu::draw(
+                menu,
+                ctx,
+                color(self.palette.foreground()),
+                color(self.palette.background()),
+            );
+        }
         if !self.redraw {
             return Ok(());
         }
         self.redraw = false;
 
-        graphics::clear(ctx, graphics::BLACK);
-        for y in 0..chip8::SCREEN_HEIGHT {
-            for x in 0..chip8::SCREEN_WIDTH {
-                let start_x = x * self.scale;
-                let start_y = y * self.scale;
-                let end_x = start_x + self.scale;
-                let end_y = start_y + self.scale;
-                let pixel = self.chip8.screen_buffer().get_pixel(x, y);
-                for render_x in start_x..end_x {
-                    for render_y in start_y..end_y {
-                        let index =
-                            (render_y as usize * self.width as usize + render_x as usize) * 4;
-                        self.buffer[index] = if pixel { 255 } else { 0 };
-                        self.buffer[index + 1] = if pixel { 255 } else { 0 };
-                        self.buffer[index + 2] = if pixel { 255 } else { 0 };
-                        self.buffer[index + 3] = 255;
-                    }
-                }
-            }
+        let screen = self.chip8.screen_buffer();
+        let resized = (screen.width(), screen.height())
+            != (self.phosphor.width(), self.phosphor.height());
+        // frames update the phosphor as they run, which they don't while
+        // paused, so a redraw alone must not make pixels fade any further
+        if self.screen_changed || resized {
+            self.phosphor.update(screen);
+            self.screen_changed = false;
+        }
+
+        graphics::clear(ctx, color(self.palette.background()));
+        let Rgb(fg_r, fg_g, fg_b) = self.palette.foreground();
+        let Rgb(bg_r, bg_g, bg_b) = self.palette.background();
+        let mix = |fg: u8, bg: u8, intensity: u8| {
+            let intensity = intensity as u32;
+            let max = MAX_INTENSITY as u32;
+            ((fg as u32 * intensity + bg as u32 * (max - intensity)) / max) as u8
+        };
+        let (width, height) = (self.phosphor.width(), self.phosphor.height());
+        self.buffer.resize(width as usize * height as usize * 4, 0);
+        let intensities = self.phosphor.intensities();
+        for (pixel, intensity) in self.buffer.chunks_exact_mut(4).zip(intensities) {
+            let color = [
+                mix(fg_r, bg_r, *intensity),
+                mix(fg_g, bg_g, *intensity),
+                mix(fg_b, bg_b, *intensity),
+                255,
+            ];
+            pixel.copy_from_slice(&color);
         }
 
-        let image = graphics::Image::from_rgba8(ctx, self.width, self.height, &self.buffer)?;
+        // uploaded as it is and scaled up by the GPU
+        let mut image = graphics::Image::from_rgba8(ctx, width, height, &self.buffer)?;
+        image.set_filter(FilterMode::Nearest);
+        let (window_width, window_height) = graphics::drawable_size(ctx);
+        let viewport = Viewport::fit(window_width as u32, window_height as u32, width, height);
+        let dst = Point2::new(viewport.x as f32, viewport.y as f32);
+        let scale = viewport.scale as f32;
+        let param = DrawParam::new().dest(dst).scale([scale, scale]);
+        graphics::draw(ctx, &image, param)?;
 
-        let dst = Point2::new(0.0, 0.0);
-        graphics::draw(ctx, &image, (dst,))?;
+        let lines = viewport.grid_lines(MIN_GRID_SCALE);
+        if self.grid && !lines.is_empty() {
+            let mut grid = MeshBuilder::new();
+            for (x, y, width, height) in lines {
+                let line = Rect::new(x as f32, y as f32, width as f32, height as f32);
+                grid.rectangle(DrawMode::fill(), line, color(self.palette.background()));
+            }
+            let grid = grid.build(ctx)?;
+            graphics::draw(ctx, &grid, DrawParam::new())?;
+        }
+
+        if self.osd {
+            let fragment = TextFragment::new(self.osd_text())
+                .color(color(self.palette.foreground()))
+                .scale(Scale::uniform(OSD_TEXT_SIZE));
+            let text = Text::new(fragment);
+            let (text_width, text_height) = text.dimensions(ctx);
+            let backdrop = Rect::new(0.0, 0.0, text_width as f32 + 8.0, text_height as f32 + 8.0);
+            let backdrop = MeshBuilder::new()
+                .rectangle(DrawMode::fill(), backdrop, color(self.palette.background()))
+                .build(ctx)?;
+            graphics::draw(ctx, &backdrop, DrawParam::new())?;
+            graphics::draw(ctx, &text, (Point2::new(4.0, 4.0),))?;
+        }
         graphics::present(ctx)
     }
 
-    fn key_down_event(&mut self, _ctx: &mut Context, key: KeyCode, _mods: KeyMods, _: bool) {
+    fn key_down_event(&mut self, ctx: &mut Context, key: KeyCode, _mods: KeyMods, _: bool) {
+        if key == KeyCode::F11 {
+            return self.toggle_fullscreen(ctx);
+        }
+        if self.menu.is_some() {
+            self.menu_key_down(ctx, key);
+            return;
+        }
+        if let Some(index) = self.hinted_key(key) {
+            self.chip8.set_key(index);
+        }
         match key {
             KeyCode::Escape => self.chip8.set_key(0x0),
             KeyCode::Key1 => self.chip8.set_key(0x1),
@@ -130,12 +719,46 @@ impl EventHandler for MainWindow {
             KeyCode::X => self.chip8.set_key(0x0),
             KeyCode::C => self.chip8.set_key(0xB),
             KeyCode::V => self.chip8.set_key(0xF),
+            KeyCode::P => {
+                self.speed.set_paused(!self.speed.paused());
+                self.redraw = true;
+            }
+            KeyCode::N => self.speed.advance(),
+            KeyCode::Tab => self.speed.set_turbo(true),
+            KeyCode::Minus => eprintln!("Speed: {}", self.speed.slower()),
+            KeyCode::Equals => eprintln!("Speed: {}", self.speed.faster()),
+            KeyCode::LBracket => self.change_cycles_per_frame(-1),
+            KeyCode::RBracket => self.change_cycles_per_frame(1),
+            KeyCode::F4 => {
+                self.osd = !self.osd;
+                self.redraw = true;
+            }
+            KeyCode::F1 => self.open_menu(),
+            KeyCode::F2 => self.switch_theme(),
+            KeyCode::F3 => {
+                self.grid = !self.grid;
+                self.redraw = true;
+            }
+            KeyCode::F5 => self.save_state(),
+            KeyCode::F9 => self.load_state(),
+            KeyCode::F10 => self.toggle_recording(),
+            KeyCode::F12 => self.screenshot(),
             _ => (),
         }
     }
 
+    fn resize_event(&mut self, ctx: &mut Context, _width: f32, _height: f32) {
+        if let Err(e) = self.fit_screen_coordinates(ctx) {
+            eprintln!("Could not resize the screen: {}", e);
+        }
+    }
+
     fn key_up_event(&mut self, _ctx: &mut Context, key: KeyCode, _mods: KeyMods) {
+        if let Some(index) = self.hinted_key(key) {
+            self.chip8.unset_key(index);
+        }
         match key {
+            KeyCode::Tab => self.speed.set_turbo(false),
             KeyCode::Escape => self.chip8.unset_key(0x0),
             KeyCode::Key1 => self.chip8.unset_key(0x1),
             KeyCode::Key2 => self.chip8.unset_key(0x2),
@@ -1,28 +1,65 @@
+use super::opcode::{self, OpCode};
+
 const MEMORY_SIZE : usize = 4096;
+const ADDR_MASK: u16 = 0xFFF; // addresses wrap around at the end of memory
 
 pub struct Memory {
-    memory: Vec<u8>
+    memory: Vec<u8>,
+    decoded: Vec<Option<OpCode>>, // decode cache indexed by address
+    code_modified: bool,          // a cached instruction was overwritten
 }
 
 impl Memory {
     pub fn new() -> Self {
         Memory {
-            memory: vec![0; MEMORY_SIZE]
+            memory: vec![0; MEMORY_SIZE],
+            decoded: vec![None; MEMORY_SIZE],
+            code_modified: false,
         }
     }
 
     pub fn load_data(&mut self, data: &[u8], offset: u16) {
-        for i in 0..data.len() {
-            let addr = offset + i as u16;
-            self.set(addr, data[i]);
+        for (i, value) in data.iter().enumerate() {
+            let addr = offset.wrapping_add(i as u16);
+            self.set(addr, *value);
         }
     }
 
     pub fn set(&mut self, addr: u16, value: u8) {
+        let addr = addr & ADDR_MASK;
         self.memory[addr as usize] = value;
+        // the byte is part of the instructions starting here and one before
+        for start in &[addr, addr.wrapping_sub(1) & ADDR_MASK] {
+            self.code_modified |= self.decoded[*start as usize].take().is_some();
+        }
+    }
+
+    // reports and resets whether code was overwritten since the last call
+    #[cfg_attr(not(feature = "dynarec"), allow(dead_code))]
+    pub fn take_code_modified(&mut self) -> bool {
+        std::mem::replace(&mut self.code_modified, false)
     }
 
     pub fn get(&self, addr: u16) -> u8 {
-        self.memory[addr as usize]
+        self.memory[(addr & ADDR_MASK) as usize]
+    }
+
+    pub fn get_u16(&self, addr: u16) -> u16 {
+        (self.get(addr) as u16) << 8 | self.get(addr.wrapping_add(1)) as u16
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.memory
+    }
+
+    #[inline]
+    pub fn decode(&mut self, addr: u16) -> OpCode {
+        let addr = addr & ADDR_MASK;
+        if let Some(opcode) = self.decoded[addr as usize] {
+            return opcode;
+        }
+        let opcode = opcode::decode(self.get_u16(addr));
+        self.decoded[addr as usize] = Some(opcode);
+        opcode
     }
 }
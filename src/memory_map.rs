@@ -0,0 +1,254 @@
+use std::fmt;
+
+// Where the interpreters kept their own data in the address space, and what a
+// program may do with each part of it. Nothing is enforced; a debugger can
+// have accesses checked against the map, see Chip8::set_check_memory.
+
+pub const FONT_START: u16 = 0x050;
+pub const PROGRAM_START: u16 = 0x200;
+pub const VIP_RESERVED_START: u16 = 0xEA0;
+
+const MEMORY_SIZE: usize = 0x1000;
+const FONT_SIZE: usize = 16 * 5;
+const BIG_FONT_SIZE: usize = 10 * 10; // the ten digits of SUPER-CHIP
+
+#[rustfmt::skip]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum RegionKind {
+    Interpreter, // code and variables of the interpreter on real hardware
+    Font,        // the small hex digits used by LD F, Vx
+    BigFont,     // the SUPER-CHIP 10 line digits
+    Program,     // the ROM and whatever it stores behind it
+    Reserved,    // VIP stack, interpreter work area and display buffer
+}
+
+impl RegionKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            RegionKind::Interpreter => "interpreter",
+            RegionKind::Font => "font",
+            RegionKind::BigFont => "big font",
+            RegionKind::Program => "program",
+            RegionKind::Reserved => "reserved",
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Permissions {
+    pub read: bool,
+    pub write: bool,
+}
+
+impl Permissions {
+    pub const READ_ONLY: Permissions = Permissions {
+        read: true,
+        write: false,
+    };
+    pub const READ_WRITE: Permissions = Permissions {
+        read: true,
+        write: true,
+    };
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Region {
+    pub kind: RegionKind,
+    pub start: usize,
+    pub end: usize, // exclusive
+    pub permissions: Permissions,
+}
+
+impl Region {
+    pub fn contains(&self, addr: usize) -> bool {
+        self.start <= addr && addr < self.end
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Access {
+    Read,
+    Write,
+}
+
+// an access the map does not allow; region is None outside of memory
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Violation {
+    pub addr: u16, // the instruction making the access
+    pub target: usize,
+    pub access: Access,
+    pub region: Option<RegionKind>,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let access = match self.access {
+            Access::Read => "read from",
+            Access::Write => "write to",
+        };
+        match self.region {
+            Some(region) => write!(
+                f,
+                "0x{:03X}: {} {} memory at 0x{:03X}",
+                self.addr,
+                access,
+                region.name(),
+                self.target
+            ),
+            None => write!(
+                f,
+                "0x{:03X}: {} 0x{:03X} outside of memory",
+                self.addr, access, self.target
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemoryMap {
+    size: usize,
+    regions: Vec<Region>, // later regions take precedence where they overlap
+}
+
+impl MemoryMap {
+    pub fn chip8() -> Self {
+        MemoryMap::layout(PROGRAM_START, FONT_START, BIG_FONT_SIZE)
+    }
+
+    // interpreters that keep their data outside of the address space, with
+    // the fonts in the otherwise unused memory below the program and the big
+    // font of big_font_size bytes directly behind the small one
+    pub fn layout(program_start: u16, font_start: u16, big_font_size: usize) -> Self {
+        let program_start = program_start as usize;
+        let mut map = MemoryMap::interpreter(program_start, font_start as usize);
+        if big_font_size > 0 {
+            map = map.with(
+                RegionKind::BigFont,
+                font_start as usize + FONT_SIZE,
+                big_font_size,
+                Permissions::READ_ONLY,
+            );
+        }
+        map.with(
+            RegionKind::Program,
+            program_start,
+            MEMORY_SIZE - program_start,
+            Permissions::READ_WRITE,
+        )
+    }
+
+    // the COSMAC VIP, which has no big font and keeps its stack and display
+    // at the end of memory
+    pub fn vip() -> Self {
+        MemoryMap::reserved(PROGRAM_START, FONT_START, VIP_RESERVED_START)
+    }
+
+    // interpreters like the VIP's, keeping their data from reserved_start to
+    // the end of memory
+    pub fn reserved(program_start: u16, font_start: u16, reserved_start: u16) -> Self {
+        let program_start = program_start as usize;
+        let reserved = reserved_start as usize;
+        MemoryMap::interpreter(program_start, font_start as usize)
+            .with(
+                RegionKind::Program,
+                program_start,
+                reserved - program_start,
+                Permissions::READ_WRITE,
+            )
+            .with(
+                RegionKind::Reserved,
+                reserved,
+                MEMORY_SIZE - reserved,
+                Permissions::READ_ONLY,
+            )
+    }
+
+    // everything below the program with the small font, common to all layouts
+    fn interpreter(program_start: usize, font_start: usize) -> Self {
+        MemoryMap::new(MEMORY_SIZE)
+            .with(
+                RegionKind::Interpreter,
+                0,
+                program_start,
+                Permissions::READ_ONLY,
+            )
+            .with(
+                RegionKind::Font,
+                font_start,
+                FONT_SIZE,
+                Permissions::READ_ONLY,
+            )
+    }
+
+    pub fn new(size: usize) -> Self {
+        MemoryMap {
+            size,
+            regions: Vec::new(),
+        }
+    }
+
+    pub fn with(
+        mut self,
+        kind: RegionKind,
+        start: usize,
+        len: usize,
+        permissions: Permissions,
+    ) -> Self {
+        self.regions.push(Region {
+            kind,
+            start,
+            end: start + len,
+            permissions,
+        });
+        self
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn regions(&self) -> &[Region] {
+        &self.regions
+    }
+
+    pub fn region(&self, kind: RegionKind) -> Option<&Region> {
+        self.regions.iter().find(|region| region.kind == kind)
+    }
+
+    pub fn region_at(&self, addr: usize) -> Option<&Region> {
+        if addr >= self.size {
+            return None;
+        }
+        self.regions
+            .iter()
+            .rev()
+            .find(|region| region.contains(addr))
+    }
+
+    // addresses no region covers may be accessed freely
+    pub fn check(&self, addr: usize, access: Access) -> Result<(), Option<RegionKind>> {
+        if addr >= self.size {
+            return Err(None);
+        }
+        match self.region_at(addr) {
+            Some(region) => {
+                let allowed = match access {
+                    Access::Read => region.permissions.read,
+                    Access::Write => region.permissions.write,
+                };
+                if allowed {
+                    Ok(())
+                } else {
+                    Err(Some(region.kind))
+                }
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+impl Default for MemoryMap {
+    fn default() -> Self {
+        MemoryMap::chip8()
+    }
+}
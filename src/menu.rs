@@ -0,0 +1,99 @@
+use ggez::graphics::{self, Color, Scale, Text, TextFragment};
+use ggez::nalgebra::Point2;
+use ggez::{Context, GameResult};
+
+use chip_8::browser::RomBrowser;
+use chip_8::database::RomInfo;
+
+const TEXT_SIZE: f32 = 16.0;
+const LINE_HEIGHT: f32 = 20.0;
+const MARGIN: f32 = 10.0;
+
+// the list of ROMs below the directory, with the selection highlighted
+pub fn draw(
+    browser: &RomBrowser,
+    ctx: &mut Context,
+    foreground: Color,
+    background: Color,
+) -> GameResult<()> {
+    graphics::clear(ctx, background);
+    let (_, height) = graphics::drawable_size(ctx);
+
+    let title = format!(
+        "{} - Up/Down to choose, Enter to start",
+        browser.dir().display()
+    );
+    draw_line(ctx, &title, 0, foreground)?;
+
+    // keep the selection visible by scrolling the list below the title
+    let reserved_lines = if browser.message().is_some() { 3 } else { 2 };
+    let visible = ((height - 2.0 * MARGIN) / LINE_HEIGHT) as usize;
+    let visible = visible.saturating_sub(reserved_lines).max(1);
+    let selected = browser.selected_index();
+    let first = if selected >= visible {
+        selected + 1 - visible
+    } else {
+        0
+    };
+
+    if browser.entries().is_empty() {
+        draw_line(ctx, "No ROMs found", 2, foreground)?;
+    }
+    for (line, entry) in browser
+        .entries()
+        .iter()
+        .enumerate()
+        .skip(first)
+        .take(visible)
+    {
+        let marker = if line == selected { ">" } else { " " };
+        let mut text = format!("{} {:<12} {:>6} B", marker, entry.name, entry.size);
+        if let Some(info) = &entry.info {
+            text.push_str(&format!("  {}", describe(info)));
+        }
+        let color = if line == selected {
+            foreground
+        } else {
+            dim(foreground, background)
+        };
+        draw_line(ctx, &text, line - first + 2, color)?;
+    }
+
+    if let Some(message) = browser.message() {
+        draw_line(ctx, message, visible + 2, foreground)?;
+    }
+
+    graphics::present(ctx)
+}
+
+fn draw_line(ctx: &mut Context, text: &str, line: usize, color: Color) -> GameResult<()> {
+    let fragment = TextFragment::new(text)
+        .color(color)
+        .scale(Scale::uniform(TEXT_SIZE));
+    let dst = Point2::new(MARGIN, MARGIN + line as f32 * LINE_HEIGHT);
+    graphics::draw(ctx, &Text::new(fragment), (dst,))
+}
+
+fn describe(info: &RomInfo) -> String {
+    let mut credits = info.authors.join(", ");
+    if let Some(release) = &info.release {
+        if !credits.is_empty() {
+            credits.push_str(", ");
+        }
+        credits.push_str(release);
+    }
+    if credits.is_empty() {
+        info.title.clone()
+    } else {
+        format!("{} ({})", info.title, credits)
+    }
+}
+
+fn dim(foreground: Color, background: Color) -> Color {
+    Color::new(
+        (foreground.r + background.r) / 2.0,
+        (foreground.g + background.g) / 2.0,
+        (foreground.b + background.b) / 2.0,
+        1.0,
+    )
+}
@@ -0,0 +1,147 @@
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use super::chip8::Chip8;
+
+const KEYS: u8 = 16;
+
+// The key presses of a play-through, for replaying it without a window. A
+// movie file has a line for every frame the held keys change on:
+//
+//     # comments and empty lines are ignored
+//     120 5     from frame 120 on key 5 is held
+//     150 58    then keys 5 and 8
+//     180 -     then none
+//     600 end   the movie is 600 frames long
+//
+// Without an end line the movie ends with the last change.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Movie {
+    changes: Vec<(u32, u16)>, // frame and the keys held from it on, one bit each
+    end: u32,
+}
+
+#[derive(Debug)]
+pub enum MovieError {
+    Io(io::Error),
+    Syntax { line: usize, message: String },
+}
+
+impl fmt::Display for MovieError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MovieError::Io(e) => e.fmt(f),
+            MovieError::Syntax { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl Error for MovieError {}
+
+impl From<io::Error> for MovieError {
+    fn from(e: io::Error) -> Self {
+        MovieError::Io(e)
+    }
+}
+
+impl Movie {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, MovieError> {
+        Movie::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, MovieError> {
+        let mut changes: Vec<(u32, u16)> = Vec::new();
+        let mut end = None;
+        for (index, line) in text.lines().enumerate() {
+            let syntax = |message: String| MovieError::Syntax {
+                line: index + 1,
+                message,
+            };
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            if end.is_some() {
+                return Err(syntax("nothing may follow the end".to_string()));
+            }
+            let mut fields = line.split_whitespace();
+            let frame = fields.next().unwrap();
+            let frame: u32 = frame
+                .parse()
+                .map_err(|_| syntax(format!("invalid frame {:?}", frame)))?;
+            if changes.last().is_some_and(|(last, _)| frame <= *last) {
+                return Err(syntax(format!(
+                    "frame {} is not after the one before",
+                    frame
+                )));
+            }
+            let keys = match (fields.next(), fields.next()) {
+                (Some(keys), None) => keys,
+                _ => return Err(syntax("expected a frame and the keys held".to_string())),
+            };
+            // keys are held until a later end, which must fit in a frame number
+            if keys != "end" && frame == u32::MAX {
+                return Err(syntax(format!("frame {} is too late for keys", frame)));
+            }
+            match keys {
+                "end" => end = Some(frame),
+                "-" => changes.push((frame, 0)),
+                _ => changes.push((frame, parse_keys(keys).map_err(syntax)?)),
+            }
+        }
+        Ok(Movie {
+            end: end.unwrap_or_else(|| changes.last().map_or(0, |(frame, _)| frame + 1)),
+            changes,
+        })
+    }
+
+    // the number of frames
+    pub fn len(&self) -> u32 {
+        self.end
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.end == 0
+    }
+
+    // the keys held during a frame, one bit each
+    pub fn keys(&self, frame: u32) -> u16 {
+        self.changes
+            .iter()
+            .take_while(|(start, _)| *start <= frame)
+            .last()
+            .map_or(0, |(_, keys)| *keys)
+    }
+
+    // Runs the machine through every frame of the movie, holding its keys,
+    // and hands it over after each frame.
+    pub fn play<E>(
+        &self,
+        chip8: &mut Chip8,
+        mut after_frame: impl FnMut(&Chip8) -> Result<(), E>,
+    ) -> Result<(), E> {
+        for frame in 0..self.end {
+            let keys = self.keys(frame);
+            for index in 0..KEYS {
+                match keys & (1 << index) {
+                    0 => chip8.unset_key(index),
+                    _ => chip8.set_key(index),
+                }
+            }
+            chip8.run_frame();
+            after_frame(chip8)?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_keys(keys: &str) -> Result<u16, String> {
+    keys.chars()
+        .try_fold(0, |held, key| match key.to_digit(16) {
+            Some(index) => Ok(held | 1 << index),
+            None => Err(format!("invalid key {:?}, expected hex digits or -", key)),
+        })
+}
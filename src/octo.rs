@@ -0,0 +1,794 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+use gif::SetParameter;
+use serde::Deserialize;
+
+use super::font::FontSet;
+use super::memory_map::PROGRAM_START;
+use super::quirks::{IndexIncrement, Quirks};
+
+const MAX_ADDRESS: u16 = 0xFFFF;
+
+// options as stored by Octo in cartridges and exported settings
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct OctoOptions {
+    pub tickrate: Option<u16>,
+    pub fill_color: Option<String>,
+    pub fill_color2: Option<String>,
+    pub blend_color: Option<String>,
+    pub background_color: Option<String>,
+    pub shift_quirks: bool,
+    pub load_store_quirks: bool,
+    pub clip_quirks: bool,
+    pub jump_quirks: bool,
+    pub logic_quirks: bool,
+    pub v_blank_quirks: bool,
+    pub font_style: Option<String>,
+}
+
+impl OctoOptions {
+    pub fn quirks(&self) -> Quirks {
+        Quirks {
+            shift_uses_vy: !self.shift_quirks,
+            load_store_index: if self.load_store_quirks {
+                IndexIncrement::Unchanged
+            } else {
+                IndexIncrement::ByXPlusOne
+            },
+            logic_resets_vf: self.logic_quirks,
+            jump_uses_vx: self.jump_quirks,
+            clip_sprites: self.clip_quirks,
+            display_wait: self.v_blank_quirks,
+            // Octo does not limit the stack
+            stack_depth: None,
+            stack_wraps: false,
+            // Octo's own fonts are not built in
+            font: self
+                .font_style
+                .as_deref()
+                .and_then(|style| style.parse().ok())
+                .unwrap_or(FontSet::Schip),
+        }
+    }
+
+    // background first, then one colour per plane; same order as the ROM database
+    pub fn colors(&self) -> Vec<String> {
+        let colors = [
+            &self.background_color,
+            &self.fill_color,
+            &self.fill_color2,
+            &self.blend_color,
+        ];
+        colors
+            .iter()
+            .take_while(|color| color.is_some())
+            .filter_map(|color| (*color).clone())
+            .collect()
+    }
+}
+
+#[derive(Deserialize)]
+struct CartridgePayload {
+    program: String,
+    #[serde(default)]
+    options: OctoOptions,
+}
+
+pub struct Cartridge {
+    pub program: String,
+    pub options: OctoOptions,
+}
+
+// Octo cartridges are GIF images carrying a JSON payload with the program
+// source and its options. The payload is spread over the pixel indices of all
+// frames: every index holds two bits in its lowest bits, four indices make a
+// byte (most significant bits first). The payload starts with its length as a
+// big-endian 32 bit number.
+pub fn read_cartridge(data: &[u8]) -> Result<Cartridge, String> {
+    let mut decoder = gif::Decoder::new(data);
+    decoder.set(gif::ColorOutput::Indexed);
+    let mut reader = decoder
+        .read_info()
+        .map_err(|e| format!("invalid GIF image: {}", e))?;
+
+    let mut indices = Vec::new();
+    while let Some(frame) = reader
+        .read_next_frame()
+        .map_err(|e| format!("invalid GIF image: {}", e))?
+    {
+        indices.extend_from_slice(&frame.buffer);
+    }
+
+    let mut bytes = indices
+        .chunks_exact(4)
+        .map(|c| (c[0] & 3) << 6 | (c[1] & 3) << 4 | (c[2] & 3) << 2 | (c[3] & 3));
+    let mut size: usize = 0;
+    for _ in 0..4 {
+        let byte = bytes.next().ok_or("cartridge is too small to hold a payload")?;
+        size = size << 8 | byte as usize;
+    }
+    let payload: Vec<u8> = bytes.take(size).collect();
+    if payload.len() < size {
+        return Err(format!(
+            "cartridge payload is truncated ({} of {} bytes)",
+            payload.len(),
+            size
+        ));
+    }
+
+    let payload: CartridgePayload = serde_json::from_slice(&payload)
+        .map_err(|e| format!("invalid cartridge payload: {}", e))?;
+    Ok(Cartridge {
+        program: payload.program,
+        options: payload.options,
+    })
+}
+
+#[derive(Debug)]
+pub struct AssembleError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AssembleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for AssembleError {}
+
+// Compiles Octo source into a CHIP-8 binary loaded at 0x200. Labels,
+// constants, aliases, structured control flow and the SUPER-CHIP and XO-CHIP
+// mnemonics are supported; macros, :calc, :stringmode and :assert are not.
+pub fn assemble(source: &str) -> Result<Vec<u8>, AssembleError> {
+    let mut assembler = Assembler::new(tokenize(source));
+    assembler.run()?;
+    Ok(assembler.rom)
+}
+
+struct Token {
+    text: String,
+    line: usize,
+}
+
+fn tokenize(source: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    for (index, line) in source.lines().enumerate() {
+        let code = match line.find('#') {
+            Some(comment) => &line[..comment],
+            None => line,
+        };
+        for text in code.split_whitespace() {
+            tokens.push(Token {
+                text: text.to_string(),
+                line: index + 1,
+            });
+        }
+    }
+    tokens
+}
+
+#[derive(Copy, Clone)]
+enum FixupKind {
+    Addr12,            // low 12 bits of the instruction word
+    Addr16,            // full word following F000
+    Byte,              // low byte of the address
+    NibbleAndHigh(u8), // given nibble followed by the high 4 bits of the address
+}
+
+struct Fixup {
+    at: u16,
+    name: String,
+    line: usize,
+    kind: FixupKind,
+}
+
+enum Value {
+    Number(i32),
+    Name(String),
+}
+
+struct Assembler {
+    tokens: Vec<Token>,
+    pos: usize,
+    line: usize,
+    rom: Vec<u8>,
+    here: u16,
+    labels: HashMap<String, u16>,
+    constants: HashMap<String, i32>,
+    aliases: HashMap<String, u8>,
+    fixups: Vec<Fixup>,
+    branches: Vec<u16>,          // jumps of open if/else blocks
+    loops: Vec<(u16, Vec<u16>)>, // start of open loops and the jumps of their whiles
+}
+
+impl Assembler {
+    fn new(tokens: Vec<Token>) -> Self {
+        Assembler {
+            tokens,
+            pos: 0,
+            line: 1,
+            rom: Vec::new(),
+            here: PROGRAM_START,
+            labels: HashMap::new(),
+            constants: HashMap::new(),
+            aliases: HashMap::new(),
+            fixups: Vec::new(),
+            branches: Vec::new(),
+            loops: Vec::new(),
+        }
+    }
+
+    fn run(&mut self) -> Result<(), AssembleError> {
+        // programs always start with a jump to main
+        self.emit_jump(0x1000, "main".to_string())?;
+        while self.pos < self.tokens.len() {
+            self.statement()?;
+        }
+
+        if !self.branches.is_empty() {
+            return Err(self.error("`begin` without matching `end`".to_string()));
+        }
+        if !self.loops.is_empty() {
+            return Err(self.error("`loop` without matching `again`".to_string()));
+        }
+        self.resolve_fixups()
+    }
+
+    fn error(&self, message: String) -> AssembleError {
+        AssembleError {
+            line: self.line,
+            message,
+        }
+    }
+
+    fn next(&mut self) -> Result<String, AssembleError> {
+        match self.tokens.get(self.pos) {
+            Some(token) => {
+                self.pos += 1;
+                self.line = token.line;
+                Ok(token.text.clone())
+            }
+            None => Err(self.error("unexpected end of program".to_string())),
+        }
+    }
+
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.pos).map(|token| token.text.as_str())
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<(), AssembleError> {
+        let token = self.next()?;
+        if token == expected {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected `{}`, found `{}`", expected, token)))
+        }
+    }
+
+    fn statement(&mut self) -> Result<(), AssembleError> {
+        let token = self.next()?;
+        match token.as_str() {
+            ":" => {
+                let name = self.next()?;
+                self.define_label(name, self.here)
+            }
+            ":next" => {
+                let name = self.next()?;
+                let addr = self.next_address()?;
+                self.define_label(name, addr)
+            }
+            ":const" => {
+                let name = self.next()?;
+                let value = self.number()?;
+                self.constants.insert(name, value);
+                Ok(())
+            }
+            ":alias" => {
+                let name = self.next()?;
+                let reg = self.register()?;
+                self.aliases.insert(name, reg);
+                Ok(())
+            }
+            ":org" => {
+                let addr = self.number()?;
+                if addr < PROGRAM_START as i32 || addr > MAX_ADDRESS as i32 {
+                    return Err(self.error(format!(":org address 0x{:X} out of range", addr)));
+                }
+                self.here = addr as u16;
+                Ok(())
+            }
+            ":byte" => {
+                let value = self.byte()?;
+                self.emit_byte(value)
+            }
+            ":unpack" => {
+                let nibble = self.number()?;
+                if !(0..=0xF).contains(&nibble) {
+                    return Err(self.error(format!("nibble {} out of range", nibble)));
+                }
+                let name = self.next()?;
+                let addr = self.next_address()?;
+                self.fixup(name.clone(), FixupKind::NibbleAndHigh(nibble as u8), addr);
+                self.emit(0x6000)?;
+                let addr = self.next_address()?;
+                self.fixup(name, FixupKind::Byte, addr);
+                self.emit(0x6100)
+            }
+            ":call" => {
+                let target = self.next()?;
+                self.emit_jump(0x2000, target)
+            }
+            ":breakpoint" => self.next().map(|_| ()),
+            ":monitor" => {
+                self.next()?;
+                self.next().map(|_| ())
+            }
+            ":macro" | ":calc" | ":stringmode" | ":assert" => {
+                Err(self.error(format!("`{}` is not supported", token)))
+            }
+            "clear" => self.emit(0x00E0),
+            "return" | ";" => self.emit(0x00EE),
+            "hires" => self.emit(0x00FF),
+            "lores" => self.emit(0x00FE),
+            "exit" => self.emit(0x00FD),
+            "scroll-right" => self.emit(0x00FB),
+            "scroll-left" => self.emit(0x00FC),
+            "scroll-down" => {
+                let n = self.nibble()?;
+                self.emit(0x00C0 | n)
+            }
+            "scroll-up" => {
+                let n = self.nibble()?;
+                self.emit(0x00D0 | n)
+            }
+            "audio" => self.emit(0xF002),
+            "plane" => {
+                let n = self.nibble()?;
+                self.emit(0xF001 | n << 8)
+            }
+            "native" => {
+                let target = self.next()?;
+                self.emit_jump(0x0000, target)
+            }
+            "jump" => {
+                let target = self.next()?;
+                self.emit_jump(0x1000, target)
+            }
+            "jump0" => {
+                let target = self.next()?;
+                self.emit_jump(0xB000, target)
+            }
+            "bcd" => self.reg_op(0xF033),
+            "saveflags" => self.reg_op(0xF075),
+            "loadflags" => self.reg_op(0xF085),
+            "save" => self.save_load(0xF055, 0x5002),
+            "load" => self.save_load(0xF065, 0x5003),
+            "sprite" => {
+                let x = self.register()? as u16;
+                let y = self.register()? as u16;
+                let n = self.nibble()?;
+                self.emit(0xD000 | x << 8 | y << 4 | n)
+            }
+            "delay" => {
+                self.expect(":=")?;
+                self.reg_op(0xF015)
+            }
+            "buzzer" => {
+                self.expect(":=")?;
+                self.reg_op(0xF018)
+            }
+            "pitch" => {
+                self.expect(":=")?;
+                self.reg_op(0xF03A)
+            }
+            "i" => self.index_statement(),
+            "if" => self.if_statement(),
+            "else" => {
+                let open = match self.branches.pop() {
+                    Some(open) => open,
+                    None => return Err(self.error("`else` without `begin`".to_string())),
+                };
+                let jump = self.here;
+                self.emit(0x1000)?;
+                self.patch_jump(open, self.here);
+                self.branches.push(jump);
+                Ok(())
+            }
+            "end" => match self.branches.pop() {
+                Some(open) => {
+                    self.patch_jump(open, self.here);
+                    Ok(())
+                }
+                None => Err(self.error("`end` without `begin`".to_string())),
+            },
+            "loop" => {
+                self.loops.push((self.here, Vec::new()));
+                Ok(())
+            }
+            "while" => {
+                if self.loops.is_empty() {
+                    return Err(self.error("`while` outside of a loop".to_string()));
+                }
+                let (skip_when_true, _) = self.condition()?;
+                self.emit(skip_when_true)?;
+                let jump = self.here;
+                self.emit(0x1000)?;
+                self.loops.last_mut().unwrap().1.push(jump);
+                Ok(())
+            }
+            "again" => match self.loops.pop() {
+                Some((start, exits)) => {
+                    self.emit(0x1000 | start & 0xFFF)?;
+                    for exit in exits {
+                        self.patch_jump(exit, self.here);
+                    }
+                    Ok(())
+                }
+                None => Err(self.error("`again` without `loop`".to_string())),
+            },
+            _ => {
+                if let Ok(reg) = self.parse_register(&token) {
+                    return self.register_statement(reg);
+                }
+                match self.parse_value(&token)? {
+                    Value::Number(value) => {
+                        let value = self.check_byte(value)?;
+                        self.emit_byte(value)
+                    }
+                    // a bare label name calls the subroutine
+                    Value::Name(name) => self.emit_jump(0x2000, name),
+                }
+            }
+        }
+    }
+
+    fn register_statement(&mut self, x: u8) -> Result<(), AssembleError> {
+        let x = x as u16;
+        let op = self.next()?;
+        let rhs = self.next()?;
+        let y = self.parse_register(&rhs).ok().map(|y| y as u16);
+        match (op.as_str(), y) {
+            (":=", _) if rhs == "delay" => self.emit(0xF007 | x << 8),
+            (":=", _) if rhs == "key" => self.emit(0xF00A | x << 8),
+            (":=", _) if rhs == "random" => {
+                let mask = self.byte()? as u16;
+                self.emit(0xC000 | x << 8 | mask)
+            }
+            (":=", Some(y)) => self.emit(0x8000 | x << 8 | y << 4),
+            ("|=", Some(y)) => self.emit(0x8001 | x << 8 | y << 4),
+            ("&=", Some(y)) => self.emit(0x8002 | x << 8 | y << 4),
+            ("^=", Some(y)) => self.emit(0x8003 | x << 8 | y << 4),
+            ("+=", Some(y)) => self.emit(0x8004 | x << 8 | y << 4),
+            ("-=", Some(y)) => self.emit(0x8005 | x << 8 | y << 4),
+            (">>=", Some(y)) => self.emit(0x8006 | x << 8 | y << 4),
+            ("=-", Some(y)) => self.emit(0x8007 | x << 8 | y << 4),
+            ("<<=", Some(y)) => self.emit(0x800E | x << 8 | y << 4),
+            (":=", None) => {
+                let value = self.byte_value(&rhs)? as u16;
+                self.emit(0x6000 | x << 8 | value)
+            }
+            ("+=", None) => {
+                let value = self.byte_value(&rhs)? as u16;
+                self.emit(0x7000 | x << 8 | value)
+            }
+            ("-=", None) => {
+                let value = self.byte_value(&rhs)?.wrapping_neg() as u16;
+                self.emit(0x7000 | x << 8 | value)
+            }
+            _ => Err(self.error(format!("unknown register operation `{} {}`", op, rhs))),
+        }
+    }
+
+    fn index_statement(&mut self) -> Result<(), AssembleError> {
+        let op = self.next()?;
+        match op.as_str() {
+            "+=" => self.reg_op(0xF01E),
+            ":=" => match self.next()?.as_str() {
+                "hex" => self.reg_op(0xF029),
+                "bighex" => self.reg_op(0xF030),
+                "long" => {
+                    let target = self.next()?;
+                    self.emit(0xF000)?;
+                    match self.parse_value(&target)? {
+                        Value::Number(addr) => {
+                            let addr = self.check_address(addr, MAX_ADDRESS)?;
+                            self.emit(addr)
+                        }
+                        Value::Name(name) => {
+                            self.fixup(name, FixupKind::Addr16, self.here);
+                            self.emit(0x0000)
+                        }
+                    }
+                }
+                target => {
+                    let target = target.to_string();
+                    self.emit_jump(0xA000, target)
+                }
+            },
+            _ => Err(self.error(format!("unknown index register operation `{}`", op))),
+        }
+    }
+
+    fn if_statement(&mut self) -> Result<(), AssembleError> {
+        let (skip_when_true, skip_when_false) = self.condition()?;
+        match self.next()?.as_str() {
+            // the following statement runs when the condition holds
+            "then" => self.emit(skip_when_false),
+            // skip the jump to else/end when the condition holds
+            "begin" => {
+                self.emit(skip_when_true)?;
+                self.branches.push(self.here);
+                self.emit(0x1000)
+            }
+            other => Err(self.error(format!("expected `then` or `begin`, found `{}`", other))),
+        }
+    }
+
+    // returns the skip instructions that skip when the condition is true and false
+    fn condition(&mut self) -> Result<(u16, u16), AssembleError> {
+        let x = self.register()? as u16;
+        let op = self.next()?;
+        match op.as_str() {
+            "key" => Ok((0xE09E | x << 8, 0xE0A1 | x << 8)),
+            "-key" => Ok((0xE0A1 | x << 8, 0xE09E | x << 8)),
+            "==" | "!=" => {
+                let rhs = self.next()?;
+                let (equal, not_equal) = match self.parse_register(&rhs) {
+                    Ok(y) => (
+                        0x5000 | x << 8 | (y as u16) << 4,
+                        0x9000 | x << 8 | (y as u16) << 4,
+                    ),
+                    Err(_) => {
+                        let value = self.byte_value(&rhs)? as u16;
+                        (0x3000 | x << 8 | value, 0x4000 | x << 8 | value)
+                    }
+                };
+                if op == "==" {
+                    Ok((equal, not_equal))
+                } else {
+                    Ok((not_equal, equal))
+                }
+            }
+            // like Octo, subtract in vF (or the register aliased compare-temp)
+            // and test the flag, which is 1 when there is no borrow
+            "<" | ">" | "<=" | ">=" => {
+                let temp = self.aliases.get("compare-temp").copied().unwrap_or(0xF) as u16;
+                let rhs = self.next()?;
+                match self.parse_register(&rhs) {
+                    Ok(y) => self.emit(0x8000 | temp << 8 | (y as u16) << 4)?,
+                    Err(_) => {
+                        let value = self.byte_value(&rhs)? as u16;
+                        self.emit(0x6000 | temp << 8 | value)?
+                    }
+                }
+                let (subtract, no_borrow) = match op.as_str() {
+                    ">" | "<=" => (0x8005, op == "<="), // temp -= x
+                    _ => (0x8007, op == ">="),          // temp =- x
+                };
+                self.emit(subtract | temp << 8 | x << 4)?;
+                if no_borrow {
+                    Ok((0x3F01, 0x4F01))
+                } else {
+                    Ok((0x4F01, 0x3F01))
+                }
+            }
+            _ => Err(self.error(format!("unsupported condition `{}`", op))),
+        }
+    }
+
+    fn reg_op(&mut self, opcode: u16) -> Result<(), AssembleError> {
+        let x = self.register()? as u16;
+        self.emit(opcode | x << 8)
+    }
+
+    fn save_load(&mut self, single: u16, range: u16) -> Result<(), AssembleError> {
+        let x = self.register()? as u16;
+        if self.peek() == Some("-") {
+            self.next()?;
+            let y = self.register()? as u16;
+            self.emit(range | x << 8 | y << 4)
+        } else {
+            self.emit(single | x << 8)
+        }
+    }
+
+    fn define_label(&mut self, name: String, addr: u16) -> Result<(), AssembleError> {
+        if self.labels.contains_key(&name) {
+            return Err(self.error(format!("label `{}` is defined twice", name)));
+        }
+        self.labels.insert(name, addr);
+        Ok(())
+    }
+
+    fn fixup(&mut self, name: String, kind: FixupKind, at: u16) {
+        self.fixups.push(Fixup {
+            at,
+            name,
+            line: self.line,
+            kind,
+        });
+    }
+
+    fn emit_jump(&mut self, opcode: u16, target: String) -> Result<(), AssembleError> {
+        match self.parse_value(&target)? {
+            Value::Number(addr) => {
+                let addr = self.check_address(addr, 0xFFF)?;
+                self.emit(opcode | addr)
+            }
+            Value::Name(name) => {
+                self.fixup(name, FixupKind::Addr12, self.here);
+                self.emit(opcode)
+            }
+        }
+    }
+
+    fn patch_jump(&mut self, at: u16, target: u16) {
+        let index = (at - PROGRAM_START) as usize;
+        self.rom[index] = (self.rom[index] & 0xF0) | ((target >> 8) & 0xF) as u8;
+        self.rom[index + 1] = target as u8;
+    }
+
+    fn resolve_fixups(&mut self) -> Result<(), AssembleError> {
+        for fixup in &self.fixups {
+            let addr = match self.labels.get(&fixup.name) {
+                Some(addr) => *addr,
+                None => {
+                    return Err(AssembleError {
+                        line: fixup.line,
+                        message: format!("undefined name `{}`", fixup.name),
+                    })
+                }
+            };
+            let index = (fixup.at - PROGRAM_START) as usize;
+            match fixup.kind {
+                FixupKind::Addr12 => {
+                    if addr > 0xFFF {
+                        return Err(AssembleError {
+                            line: fixup.line,
+                            message: format!("label `{}` is out of range", fixup.name),
+                        });
+                    }
+                    self.rom[index] |= (addr >> 8) as u8;
+                    self.rom[index + 1] = addr as u8;
+                }
+                FixupKind::Addr16 => {
+                    self.rom[index] = (addr >> 8) as u8;
+                    self.rom[index + 1] = addr as u8;
+                }
+                FixupKind::Byte => self.rom[index] = addr as u8,
+                FixupKind::NibbleAndHigh(nibble) => {
+                    self.rom[index] = nibble << 4 | ((addr >> 8) & 0xF) as u8
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn emit(&mut self, word: u16) -> Result<(), AssembleError> {
+        self.emit_byte((word >> 8) as u8)?;
+        self.emit_byte(word as u8)
+    }
+
+    // the address of the second byte of what comes next
+    fn next_address(&self) -> Result<u16, AssembleError> {
+        self.here
+            .checked_add(1)
+            .ok_or_else(|| self.error("program does not fit into memory".to_string()))
+    }
+
+    fn emit_byte(&mut self, value: u8) -> Result<(), AssembleError> {
+        if self.here == MAX_ADDRESS {
+            return Err(self.error("program does not fit into memory".to_string()));
+        }
+        let index = (self.here - PROGRAM_START) as usize;
+        if index >= self.rom.len() {
+            self.rom.resize(index + 1, 0);
+        }
+        self.rom[index] = value;
+        self.here += 1;
+        Ok(())
+    }
+
+    fn register(&mut self) -> Result<u8, AssembleError> {
+        let token = self.next()?;
+        self.parse_register(&token)
+    }
+
+    fn parse_register(&self, token: &str) -> Result<u8, AssembleError> {
+        if let Some(reg) = self.aliases.get(token) {
+            return Ok(*reg);
+        }
+        let lower = token.to_lowercase();
+        if lower.len() == 2 && lower.starts_with('v') {
+            if let Ok(reg) = u8::from_str_radix(&lower[1..], 16) {
+                return Ok(reg);
+            }
+        }
+        Err(self.error(format!("expected a register, found `{}`", token)))
+    }
+
+    fn number(&mut self) -> Result<i32, AssembleError> {
+        let token = self.next()?;
+        match self.parse_value(&token)? {
+            Value::Number(value) => Ok(value),
+            Value::Name(name) => match self.labels.get(&name) {
+                Some(addr) => Ok(*addr as i32),
+                None => Err(self.error(format!("expected a number, found `{}`", name))),
+            },
+        }
+    }
+
+    fn nibble(&mut self) -> Result<u16, AssembleError> {
+        let value = self.number()?;
+        if (0..=0xF).contains(&value) {
+            Ok(value as u16)
+        } else {
+            Err(self.error(format!("{} does not fit into 4 bits", value)))
+        }
+    }
+
+    fn byte(&mut self) -> Result<u8, AssembleError> {
+        let token = self.next()?;
+        self.byte_value(&token)
+    }
+
+    fn byte_value(&self, token: &str) -> Result<u8, AssembleError> {
+        match self.parse_value(token)? {
+            Value::Number(value) => self.check_byte(value),
+            Value::Name(name) => Err(self.error(format!("expected a number, found `{}`", name))),
+        }
+    }
+
+    fn check_byte(&self, value: i32) -> Result<u8, AssembleError> {
+        if (-128..=255).contains(&value) {
+            Ok(value as u8)
+        } else {
+            Err(self.error(format!("{} does not fit into a byte", value)))
+        }
+    }
+
+    fn check_address(&self, value: i32, max: u16) -> Result<u16, AssembleError> {
+        if value >= 0 && value <= max as i32 {
+            Ok(value as u16)
+        } else {
+            Err(self.error(format!("address {} is out of range", value)))
+        }
+    }
+
+    fn parse_value(&self, token: &str) -> Result<Value, AssembleError> {
+        if let Some(value) = self.constants.get(token) {
+            return Ok(Value::Number(*value));
+        }
+        let (negative, digits) = match token.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, token),
+        };
+        // from_str_radix takes a sign of its own, which Octo doesn't
+        let unsigned = |rest: &str| !rest.starts_with(['+', '-']);
+        let parsed = if let Some(hex) = digits.strip_prefix("0x").filter(|h| unsigned(h)) {
+            i32::from_str_radix(hex, 16).ok()
+        } else if let Some(bin) = digits.strip_prefix("0b").filter(|b| unsigned(b)) {
+            i32::from_str_radix(bin, 2).ok()
+        } else if digits.starts_with(|c: char| c.is_ascii_digit()) {
+            digits.parse::<i32>().ok()
+        } else {
+            None
+        };
+        match parsed {
+            Some(value) if negative => match value.checked_neg() {
+                Some(value) => Ok(Value::Number(value)),
+                None => Err(self.error(format!("number `{}` is out of range", token))),
+            },
+            Some(value) => Ok(Value::Number(value)),
+            None if token.starts_with(|c: char| c.is_ascii_digit() || c == '-') => {
+                Err(self.error(format!("invalid number `{}`", token)))
+            }
+            None => Ok(Value::Name(token.to_string())),
+        }
+    }
+}
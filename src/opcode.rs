@@ -1,5 +1,8 @@
-#[cfg_attr(rustfmt, rustfmt_skip)]
-#[derive(Debug)]
+use std::fmt;
+
+#[rustfmt::skip]
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub enum OpCode {
     SYS {addr: u16},                // 0nnn; System call (ignored)
     CLR,                            // 00E0; Clear the screen
@@ -17,8 +20,9 @@ pub enum OpCode {
     XOR { s: u8, t: u8 },           // 8st3; Perform logical XOR on register s and t and store in t
     ADDR { s: u8, t: u8 },          // 8st4; Add s to t and store in s - register F set on carry
     SUB { s: u8, t: u8 },           // 8st5; Subtract s from t and store in s - register F set on !borrow
-    SHR { s: u8 },                  // 8s06; Shift bits in register s 1 bit to the right - bit 0 shifts to register F
-    SHL { s: u8 },                  // 8s0E; Shift bits in register s 1 bit to the left - bit 7 shifts to register F
+    SHR { s: u8, t: u8 },           // 8st6; Shift bits in register s 1 bit to the right - bit 0 shifts to register F
+    SUBN { s: u8, t: u8 },          // 8st7; Subtract s from t and store in s - register F set on !borrow
+    SHL { s: u8, t: u8 },           // 8stE; Shift bits in register s 1 bit to the left - bit 7 shifts to register F
     SKRNE { s: u8, t: u8 },         // 9st0; Skip next instruction if register s not equal register t
     LOADI { addr: u16 },            // Annn; Load index with value nnn
     JUMPI { addr: u16 },            // Bnnn; Jump to address nnn + index
@@ -35,13 +39,14 @@ pub enum OpCode {
     BCD { s: u8 },                  // Fs33; Store the binary coded decimal value of register s at index
     STOR { s: u8 },                 // Fs55; Store the values of register s registers at index
     READ { s: u8 },                 // Fs65; Read back the stored values at index into registers
+    Unknown(u16),                   // any other value, kept for reporting
 }
 
 pub fn decode(val: u16) -> OpCode {
     //println!("Decoding opcode 0x{:X}", val);
     let first_nibble = val & 0xF000;
     match first_nibble {
-        0x0000 => match get_n34(val) {
+        0x0000 => match val {
             0x00E0 => OpCode::CLR,
             0x00EE => OpCode::RET,
             _ => OpCode::SYS {
@@ -59,34 +64,35 @@ pub fn decode(val: u16) -> OpCode {
             nn: get_n34(val),
         },
         0x4000 => OpCode::SKNE {
-            s: get_n2(val) as u8,
+            s: get_n2(val),
             nn: get_n34(val),
         },
         0x5000 => OpCode::SKRE {
-            s: get_n2(val) as u8,
+            s: get_n2(val),
             t: get_n3(val),
         },
         0x6000 => OpCode::LOAD {
-            s: get_n2(val) as u8,
+            s: get_n2(val),
             nn: get_n34(val),
         },
         0x7000 => OpCode::ADD {
-            s: get_n2(val) as u8,
+            s: get_n2(val),
             nn: get_n34(val),
         },
         0x8000 => {
             let s = get_n2(val);
             let t = get_n3(val);
             match get_n4(val) {
-                0x00 => OpCode::MOVE { s: s, t: t },
-                0x01 => OpCode::OR { s: s, t: t },
-                0x02 => OpCode::AND { s: s, t: t },
-                0x03 => OpCode::XOR { s: s, t: t },
-                0x04 => OpCode::ADDR { s: s, t: t },
-                0x05 => OpCode::SUB { s: s, t: t },
-                0x06 => OpCode::SHR { s: s },
-                0x0E => OpCode::SHL { s: s },
-                _ => panic!("Invalid OpCode 0x{:X}", val),
+                0x00 => OpCode::MOVE { s, t },
+                0x01 => OpCode::OR { s, t },
+                0x02 => OpCode::AND { s, t },
+                0x03 => OpCode::XOR { s, t },
+                0x04 => OpCode::ADDR { s, t },
+                0x05 => OpCode::SUB { s, t },
+                0x06 => OpCode::SHR { s, t },
+                0x07 => OpCode::SUBN { s, t },
+                0x0E => OpCode::SHL { s, t },
+                _ => OpCode::Unknown(val),
             }
         }
         0x9000 => OpCode::SKRNE {
@@ -111,11 +117,9 @@ pub fn decode(val: u16) -> OpCode {
         0xE000 => {
             let s = get_n2(val);
             match get_n34(val) {
-                0x009E => OpCode::SKP { s: s },
-                0x00A1 => OpCode::SKNP { s: s },
-                _ => OpCode::SYS {
-                    addr: get_n234(val),
-                },
+                0x009E => OpCode::SKP { s },
+                0x00A1 => OpCode::SKNP { s },
+                _ => OpCode::Unknown(val),
             }
         }
         0xF000 => {
@@ -131,10 +135,10 @@ pub fn decode(val: u16) -> OpCode {
                 0x0033 => OpCode::BCD { s: reg },
                 0x0055 => OpCode::STOR { s: reg },
                 0x0065 => OpCode::READ { s: reg },
-                _ => panic!("Invalid OpCode 0x{:X}", val),
+                _ => OpCode::Unknown(val),
             }
         }
-        _ => panic!("Invalid OpCode 0x{:X}", val),
+        _ => OpCode::Unknown(val),
     }
 }
 
@@ -162,3 +166,47 @@ fn get_n34(opcode: u16) -> u8 {
 fn get_n234(opcode: u16) -> u16 {
     opcode & 0x0FFF
 }
+
+// disassembly using the mnemonics above, registers as V0-VF
+impl fmt::Display for OpCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            OpCode::SYS { addr } => write!(f, "SYS 0x{:03X}", addr),
+            OpCode::CLR => write!(f, "CLR"),
+            OpCode::RET => write!(f, "RET"),
+            OpCode::JUMP { addr } => write!(f, "JUMP 0x{:03X}", addr),
+            OpCode::CALL { addr } => write!(f, "CALL 0x{:03X}", addr),
+            OpCode::SKE { s, nn } => write!(f, "SKE V{:X}, 0x{:02X}", s, nn),
+            OpCode::SKNE { s, nn } => write!(f, "SKNE V{:X}, 0x{:02X}", s, nn),
+            OpCode::SKRE { s, t } => write!(f, "SKRE V{:X}, V{:X}", s, t),
+            OpCode::LOAD { s, nn } => write!(f, "LOAD V{:X}, 0x{:02X}", s, nn),
+            OpCode::ADD { s, nn } => write!(f, "ADD V{:X}, 0x{:02X}", s, nn),
+            OpCode::MOVE { s, t } => write!(f, "MOVE V{:X}, V{:X}", s, t),
+            OpCode::OR { s, t } => write!(f, "OR V{:X}, V{:X}", s, t),
+            OpCode::AND { s, t } => write!(f, "AND V{:X}, V{:X}", s, t),
+            OpCode::XOR { s, t } => write!(f, "XOR V{:X}, V{:X}", s, t),
+            OpCode::ADDR { s, t } => write!(f, "ADDR V{:X}, V{:X}", s, t),
+            OpCode::SUB { s, t } => write!(f, "SUB V{:X}, V{:X}", s, t),
+            OpCode::SHR { s, t } => write!(f, "SHR V{:X}, V{:X}", s, t),
+            OpCode::SUBN { s, t } => write!(f, "SUBN V{:X}, V{:X}", s, t),
+            OpCode::SHL { s, t } => write!(f, "SHL V{:X}, V{:X}", s, t),
+            OpCode::SKRNE { s, t } => write!(f, "SKRNE V{:X}, V{:X}", s, t),
+            OpCode::LOADI { addr } => write!(f, "LOADI 0x{:03X}", addr),
+            OpCode::JUMPI { addr } => write!(f, "JUMPI 0x{:03X}", addr),
+            OpCode::RAND { s, nn } => write!(f, "RAND V{:X}, 0x{:02X}", s, nn),
+            OpCode::DRAW { s, t, n } => write!(f, "DRAW V{:X}, V{:X}, {}", s, t, n),
+            OpCode::SKP { s } => write!(f, "SKP V{:X}", s),
+            OpCode::SKNP { s } => write!(f, "SKNP V{:X}", s),
+            OpCode::MOVED { s } => write!(f, "MOVED V{:X}", s),
+            OpCode::KEYD { s } => write!(f, "KEYD V{:X}", s),
+            OpCode::LOADD { s } => write!(f, "LOADD V{:X}", s),
+            OpCode::LOADS { s } => write!(f, "LOADS V{:X}", s),
+            OpCode::ADDI { s } => write!(f, "ADDI V{:X}", s),
+            OpCode::LDSPR { s } => write!(f, "LDSPR V{:X}", s),
+            OpCode::BCD { s } => write!(f, "BCD V{:X}", s),
+            OpCode::STOR { s } => write!(f, "STOR V{:X}", s),
+            OpCode::READ { s } => write!(f, "READ V{:X}", s),
+            OpCode::Unknown(raw) => write!(f, "??? 0x{:04X}", raw),
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl FromStr for Rgb {
+    type Err = String;
+
+    // "#RRGGBB", the leading # is optional
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = s.trim_start_matches('#');
+        if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!("invalid colour {:?}, expected #RRGGBB", s));
+        }
+        Ok(rgb(u32::from_str_radix(hex, 16).unwrap()))
+    }
+}
+
+const fn rgb(value: u32) -> Rgb {
+    Rgb((value >> 16) as u8, (value >> 8) as u8, value as u8)
+}
+
+impl fmt::Display for Rgb {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{:02X}{:02X}{:02X}", self.0, self.1, self.2)
+    }
+}
+
+// Background first, then one colour per combination of XO-CHIP planes; the
+// same order as in the ROM database and Octo cartridges. CHIP-8 only uses
+// the first two.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Palette {
+    pub colors: [Rgb; 4],
+}
+
+impl Palette {
+    pub fn background(&self) -> Rgb {
+        self.colors[0]
+    }
+
+    pub fn foreground(&self) -> Rgb {
+        self.colors[1]
+    }
+
+    // colours given as "#RRGGBB" replace those at the same index, invalid
+    // ones are skipped
+    pub fn with_colors(mut self, colors: &[String]) -> Self {
+        for (color, value) in self.colors.iter_mut().zip(colors) {
+            if let Ok(value) = value.parse() {
+                *color = value;
+            }
+        }
+        self
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Theme::Classic.palette()
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Theme {
+    Classic,       // white on black
+    GreenPhosphor, // P1 phosphor of early monochrome monitors
+    Amber,         // P3 phosphor
+    Lcd,           // greenish reflective LCD of handhelds
+    Octo,          // the defaults of the Octo IDE
+}
+
+impl Theme {
+    pub const NAMES: &'static [&'static str] = &["classic", "green", "amber", "lcd", "octo"];
+    pub const ALL: &'static [Theme] = &[
+        Theme::Classic,
+        Theme::GreenPhosphor,
+        Theme::Amber,
+        Theme::Lcd,
+        Theme::Octo,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Theme::Classic => "classic",
+            Theme::GreenPhosphor => "green",
+            Theme::Amber => "amber",
+            Theme::Lcd => "lcd",
+            Theme::Octo => "octo",
+        }
+    }
+
+    #[rustfmt::skip]
+    pub fn palette(self) -> Palette {
+        let colors = match self {
+            Theme::Classic =>       [0x000000, 0xFFFFFF, 0xAAAAAA, 0x555555],
+            Theme::GreenPhosphor => [0x0A140A, 0x33FF66, 0x1F9940, 0x124D22],
+            Theme::Amber =>         [0x140C00, 0xFFB000, 0xB37B00, 0x664600],
+            Theme::Lcd =>           [0x9BBC0F, 0x0F380F, 0x306230, 0x8BAC0F],
+            Theme::Octo =>          [0x996600, 0xFFCC00, 0xFF6600, 0x662200],
+        };
+        Palette {
+            colors: colors.map(rgb),
+        }
+    }
+
+    // the theme after this one, for switching through them
+    pub fn next(self) -> Theme {
+        let index = Theme::ALL.iter().position(|theme| *theme == self).unwrap();
+        Theme::ALL[(index + 1) % Theme::ALL.len()]
+    }
+}
+
+impl FromStr for Theme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "classic" => Ok(Theme::Classic),
+            "green" => Ok(Theme::GreenPhosphor),
+            "amber" => Ok(Theme::Amber),
+            "lcd" => Ok(Theme::Lcd),
+            "octo" => Ok(Theme::Octo),
+            _ => Err(format!(
+                "unknown theme {:?}, expected one of {}",
+                s,
+                Theme::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Theme {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
@@ -0,0 +1,113 @@
+use std::collections::VecDeque;
+
+use super::screen_buffer::ScreenBuffer;
+
+pub const MAX_INTENSITY: u8 = 255;
+
+// How pixels that were switched off stay visible. Sprites are erased and
+// redrawn with XOR, so without any persistence moving sprites flicker far
+// more than they did on the CRTs of the time.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum Persistence {
+    #[default]
+    Off,
+    Fade(u8),  // pixels fade out linearly over this many frames
+    Blend(u8), // show every pixel lit in any of the last frames
+}
+
+// Post-processing of the screen for display, updated once per frame. The
+// output is an intensity per pixel, row by row, which the renderer mixes
+// between background and foreground colour.
+pub struct Phosphor {
+    persistence: Persistence,
+    width: u16,
+    height: u16,
+    intensities: Vec<u8>,
+    history: VecDeque<Vec<bool>>, // the last frames for blending, newest first
+}
+
+impl Phosphor {
+    pub fn new(persistence: Persistence) -> Self {
+        Phosphor {
+            persistence,
+            width: 0,
+            height: 0,
+            intensities: Vec::new(),
+            history: VecDeque::new(),
+        }
+    }
+
+    pub fn persistence(&self) -> Persistence {
+        self.persistence
+    }
+
+    pub fn set_persistence(&mut self, persistence: Persistence) {
+        self.persistence = persistence;
+        self.history.clear();
+    }
+
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    pub fn intensities(&self) -> &[u8] {
+        &self.intensities
+    }
+
+    pub fn intensity(&self, x: u16, y: u16) -> u8 {
+        self.intensities[y as usize * self.width as usize + x as usize]
+    }
+
+    // returns whether any intensity changed, so the screen needs drawing
+    pub fn update(&mut self, screen: &ScreenBuffer) -> bool {
+        let resized = (screen.width(), screen.height()) != (self.width, self.height);
+        if resized {
+            self.width = screen.width();
+            self.height = screen.height();
+            self.intensities = vec![0; self.width as usize * self.height as usize];
+            self.history.clear();
+        }
+        let pixels: Vec<bool> = (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| screen.get_pixel(x, y)))
+            .collect();
+
+        let intensities: Vec<u8> = match self.persistence {
+            Persistence::Off => pixels.iter().map(|lit| lit_intensity(*lit)).collect(),
+            Persistence::Fade(frames) => {
+                let step = MAX_INTENSITY.div_ceil(frames.max(1));
+                pixels
+                    .iter()
+                    .zip(&self.intensities)
+                    .map(|(lit, intensity)| match lit {
+                        true => MAX_INTENSITY,
+                        false => intensity.saturating_sub(step),
+                    })
+                    .collect()
+            }
+            Persistence::Blend(frames) => {
+                self.history.truncate(frames.max(1) as usize - 1);
+                let intensities = (0..pixels.len())
+                    .map(|i| pixels[i] || self.history.iter().any(|frame| frame[i]))
+                    .map(lit_intensity)
+                    .collect();
+                self.history.push_front(pixels);
+                intensities
+            }
+        };
+        let changed = resized || intensities != self.intensities;
+        self.intensities = intensities;
+        changed
+    }
+}
+
+fn lit_intensity(lit: bool) -> u8 {
+    if lit {
+        MAX_INTENSITY
+    } else {
+        0
+    }
+}
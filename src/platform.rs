@@ -0,0 +1,119 @@
+use std::fmt;
+
+use super::font::{Font, FontSet};
+use super::memory_map::{MemoryMap, FONT_START, PROGRAM_START, VIP_RESERVED_START};
+use super::quirks::{QuirkPreset, Quirks};
+
+const ETI_660_START: u16 = 0x600;
+const MEMORY_SIZE: usize = 0x1000;
+
+// A machine programs were written for: where they are loaded, what the
+// interpreter keeps at the end of memory, the size of the screen, and the font
+// and quirks of its interpreter. All of them have 4 KiB of memory with the
+// font at FONT_START.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Descriptor {
+    pub name: &'static str,
+    pub load_address: u16,
+    pub reserved: Option<u16>, // start of the interpreter's stack and display buffer
+    pub screen_width: u16,
+    pub screen_height: u16,
+    pub quirks: QuirkPreset,
+    pub font: FontSet,
+}
+
+const fn descriptor(
+    name: &'static str,
+    load_address: u16,
+    reserved: Option<u16>,
+    (screen_width, screen_height): (u16, u16),
+    quirks: QuirkPreset,
+    font: FontSet,
+) -> Descriptor {
+    Descriptor {
+        name,
+        load_address,
+        reserved,
+        screen_width,
+        screen_height,
+        quirks,
+        font,
+    }
+}
+
+#[rustfmt::skip]
+pub static DESCRIPTORS: &[Descriptor] = &[
+    descriptor("chip8",        PROGRAM_START, None,                     (64, 32), QuirkPreset::Chip8, FontSet::Chip48),
+    descriptor("vip",          PROGRAM_START, Some(VIP_RESERVED_START), (64, 32), QuirkPreset::Vip,   FontSet::Vip),
+    descriptor("dream6800",    PROGRAM_START, None,                     (64, 32), QuirkPreset::Vip,   FontSet::Dream6800),
+    descriptor("eti660",       ETI_660_START, None,                     (64, 32), QuirkPreset::Vip,   FontSet::Eti660),
+    descriptor("eti660-64x48", ETI_660_START, None,                     (64, 48), QuirkPreset::Vip,   FontSet::Eti660),
+    descriptor("eti660-64x64", ETI_660_START, None,                     (64, 64), QuirkPreset::Vip,   FontSet::Eti660),
+    descriptor("schip",        PROGRAM_START, None,                     (64, 32), QuirkPreset::Schip, FontSet::Schip),
+];
+
+impl Descriptor {
+    // for the command line, in the order of the table
+    pub const NAMES: &'static [&'static str] = &[
+        "chip8",
+        "vip",
+        "dream6800",
+        "eti660",
+        "eti660-64x48",
+        "eti660-64x64",
+        "schip",
+    ];
+
+    pub fn find(name: &str) -> Option<&'static Descriptor> {
+        DESCRIPTORS
+            .iter()
+            .find(|descriptor| descriptor.name.eq_ignore_ascii_case(name))
+    }
+
+    // for a platform id of the ROM database
+    pub fn for_database(id: &str) -> Option<&'static Descriptor> {
+        match id {
+            "originalChip8" | "hybridVIP" => Descriptor::find("vip"),
+            "modernChip8" => Descriptor::find("chip8"),
+            "chip48" | "superchip1" | "superchip" => Descriptor::find("schip"),
+            "dream6800" | "eti660" => Descriptor::find(id),
+            _ => None,
+        }
+    }
+
+    pub fn quirks(&self) -> Quirks {
+        Quirks {
+            font: self.font,
+            ..Quirks::from_preset(self.quirks)
+        }
+    }
+
+    // with the big font region sized for the font in use
+    pub fn memory_map(&self, font: &Font) -> MemoryMap {
+        let big_font_size = font.big().map_or(0, |big| big.len());
+        match self.reserved {
+            Some(reserved) => MemoryMap::reserved(self.load_address, FONT_START, reserved),
+            None => MemoryMap::layout(self.load_address, FONT_START, big_font_size),
+        }
+    }
+
+    // up to the interpreter's reserved area if there is one
+    pub fn max_rom_size(&self) -> usize {
+        let end = self
+            .reserved
+            .map_or(MEMORY_SIZE, |reserved| reserved as usize);
+        end - self.load_address as usize
+    }
+}
+
+impl Default for Descriptor {
+    fn default() -> Self {
+        DESCRIPTORS[0].clone()
+    }
+}
+
+impl fmt::Display for Descriptor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name)
+    }
+}
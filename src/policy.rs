@@ -0,0 +1,39 @@
+use std::str::FromStr;
+
+use super::chip8::Chip8;
+
+// What the machine does on 0nnn machine code routines, which cannot be run,
+// and on values that are no instruction at all.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum OpcodePolicy {
+    Ignore, // skip the instruction
+    Warn,   // skip the instruction and count it, see Chip8::warnings
+    Halt,   // stop with a fault
+    Trap,   // call the handler set with Chip8::set_trap, halt without one
+}
+
+impl OpcodePolicy {
+    // a trap handler can only be set from code
+    pub const NAMES: &'static [&'static str] = &["ignore", "warn", "halt"];
+}
+
+impl FromStr for OpcodePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "ignore" => Ok(OpcodePolicy::Ignore),
+            "warn" => Ok(OpcodePolicy::Warn),
+            "halt" => Ok(OpcodePolicy::Halt),
+            _ => Err(format!(
+                "unknown opcode policy {:?}, expected one of {}",
+                s,
+                OpcodePolicy::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+// Called with the machine and the raw opcode; the PC already points to the
+// next instruction. Returning false halts the machine.
+pub type Trap = Box<dyn FnMut(&mut Chip8, u16) -> bool>;
@@ -0,0 +1,112 @@
+use std::fmt;
+use std::str::FromStr;
+
+use super::font::FontSet;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Quirks {
+    pub shift_uses_vy: bool,              // 8xy6/8xyE shift register y into register x instead of shifting x in place
+    pub load_store_index: IndexIncrement, // how Fx55/Fx65 change the index register
+    pub logic_resets_vf: bool,            // 8xy1/8xy2/8xy3 reset register F to 0
+    pub jump_uses_vx: bool,               // Bxnn jumps to xnn + register x instead of nnn + register 0
+    pub clip_sprites: bool,               // sprites are clipped at the screen edges instead of wrapping around
+    pub display_wait: bool,               // Dxyn waits for the next frame, see Chip8::run_frame
+    pub stack_depth: Option<usize>,       // entries on the call stack, None for unlimited
+    pub stack_wraps: bool,                // stack over- and underflow wrap around instead of halting
+    pub font: FontSet,                    // glyphs of the hex digits, see Chip8::set_font for others
+}
+
+impl Quirks {
+    pub fn from_preset(preset: QuirkPreset) -> Self {
+        match preset {
+            QuirkPreset::Chip8 => Quirks {
+                shift_uses_vy: false,
+                load_store_index: IndexIncrement::Unchanged,
+                logic_resets_vf: false,
+                jump_uses_vx: false,
+                clip_sprites: false,
+                display_wait: false,
+                stack_depth: Some(16),
+                stack_wraps: false,
+                font: FontSet::Chip48,
+            },
+            QuirkPreset::Vip => Quirks {
+                shift_uses_vy: true,
+                load_store_index: IndexIncrement::ByXPlusOne,
+                logic_resets_vf: true,
+                jump_uses_vx: false,
+                clip_sprites: true,
+                display_wait: true,
+                stack_depth: Some(12),
+                stack_wraps: true,
+                font: FontSet::Vip,
+            },
+            QuirkPreset::Schip => Quirks {
+                shift_uses_vy: false,
+                load_store_index: IndexIncrement::Unchanged,
+                logic_resets_vf: false,
+                jump_uses_vx: true,
+                clip_sprites: true,
+                display_wait: false,
+                stack_depth: Some(16),
+                stack_wraps: false,
+                font: FontSet::Schip,
+            },
+        }
+    }
+}
+
+impl Default for Quirks {
+    fn default() -> Self {
+        Quirks::from_preset(QuirkPreset::Chip8)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum IndexIncrement {
+    Unchanged,  // index register keeps its value (SUPER-CHIP 1.1)
+    ByX,        // index register is incremented by x (CHIP-48)
+    ByXPlusOne, // index register points past the last register (COSMAC VIP)
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum QuirkPreset {
+    Chip8, // behaviour of this emulator before quirks were configurable
+    Vip,   // original COSMAC VIP interpreter
+    Schip, // SUPER-CHIP 1.1 on the HP48
+}
+
+impl QuirkPreset {
+    pub const NAMES: &'static [&'static str] = &["chip8", "vip", "schip"];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            QuirkPreset::Chip8 => "chip8",
+            QuirkPreset::Vip => "vip",
+            QuirkPreset::Schip => "schip",
+        }
+    }
+}
+
+impl FromStr for QuirkPreset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "chip8" => Ok(QuirkPreset::Chip8),
+            "vip" => Ok(QuirkPreset::Vip),
+            "schip" => Ok(QuirkPreset::Schip),
+            _ => Err(format!(
+                "unknown quirk preset {:?}, expected one of {}",
+                s,
+                QuirkPreset::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+impl fmt::Display for QuirkPreset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
@@ -0,0 +1,263 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use gif::SetParameter;
+
+use super::palette::{Palette, Rgb};
+use super::screen_buffer::ScreenBuffer;
+
+const FRAMES_PER_SECOND: u32 = 60;
+const GIF_TICKS_PER_SECOND: u32 = 100; // GIF delays are in hundredths of a second
+const MAX_MERGED_FRAMES: u32 = 30_000; // keeps the delay of merged frames within u16
+
+// An animated GIF to share, or a raw YUV4MPEG2 stream to pipe into an encoder
+// such as ffmpeg.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum VideoFormat {
+    Gif,
+    Y4m,
+}
+
+impl VideoFormat {
+    pub const NAMES: &'static [&'static str] = &["gif", "y4m"];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            VideoFormat::Gif => "gif",
+            VideoFormat::Y4m => "y4m",
+        }
+    }
+
+    // by the extension of the file, "-" is a Y4M stream on standard output
+    pub fn for_path(path: &str) -> Option<VideoFormat> {
+        if path == "-" {
+            return Some(VideoFormat::Y4m);
+        }
+        let extension = Path::new(path).extension()?.to_str()?;
+        extension.parse().ok()
+    }
+}
+
+impl FromStr for VideoFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "gif" => Ok(VideoFormat::Gif),
+            "y4m" => Ok(VideoFormat::Y4m),
+            _ => Err(format!(
+                "unknown video format {:?}, expected one of {}",
+                s,
+                VideoFormat::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+impl fmt::Display for VideoFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+enum Output<W: Write> {
+    Gif {
+        encoder: gif::Encoder<W>,
+        pending: Option<(Vec<u8>, u32)>, // the last frame and for how many frames it showed
+        written: u32,                    // frames written so far, for rounding the delays
+    },
+    Y4m(W),
+}
+
+// Captures the screen once per 60 Hz frame. All frames have the size of the
+// screen the recorder was created for, each pixel scale times scale large.
+pub struct Recorder<W: Write> {
+    palette: Palette,
+    scale: u16,
+    width: u16,
+    height: u16,
+    frames: u32,
+    output: Output<W>,
+}
+
+impl Recorder<Box<dyn Write>> {
+    // in the format for_path finds for the path
+    pub fn create(
+        path: &str,
+        width: u16,
+        height: u16,
+        palette: Palette,
+        scale: u16,
+    ) -> io::Result<Self> {
+        let format = VideoFormat::for_path(path).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "expected a .gif or .y4m file or - for standard output",
+            )
+        })?;
+        let writer: Box<dyn Write> = match path {
+            "-" => Box::new(io::stdout()),
+            _ => Box::new(BufWriter::new(File::create(path)?)),
+        };
+        Recorder::new(writer, format, width, height, palette, scale)
+    }
+}
+
+impl<W: Write> Recorder<W> {
+    pub fn new(
+        mut writer: W,
+        format: VideoFormat,
+        width: u16,
+        height: u16,
+        palette: Palette,
+        scale: u16,
+    ) -> io::Result<Self> {
+        let scale = scale.max(1);
+        let output = match format {
+            VideoFormat::Gif => {
+                let colors: Vec<u8> = palette
+                    .colors
+                    .iter()
+                    .flat_map(|Rgb(r, g, b)| vec![*r, *g, *b])
+                    .collect();
+                let mut encoder =
+                    gif::Encoder::new(writer, width * scale, height * scale, &colors)?;
+                encoder.set(gif::Repeat::Infinite)?;
+                Output::Gif {
+                    encoder,
+                    pending: None,
+                    written: 0,
+                }
+            }
+            VideoFormat::Y4m => {
+                writeln!(
+                    writer,
+                    "YUV4MPEG2 W{} H{} F{}:1 Ip A1:1 C444",
+                    width * scale,
+                    height * scale,
+                    FRAMES_PER_SECOND
+                )?;
+                Output::Y4m(writer)
+            }
+        };
+        Ok(Recorder {
+            palette,
+            scale,
+            width,
+            height,
+            frames: 0,
+            output,
+        })
+    }
+
+    pub fn frames(&self) -> u32 {
+        self.frames
+    }
+
+    pub fn frame(&mut self, screen: &ScreenBuffer) -> io::Result<()> {
+        if (screen.width(), screen.height()) != (self.width, self.height) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "the screen changed to {}x{} while recording at {}x{}",
+                    screen.width(),
+                    screen.height(),
+                    self.width,
+                    self.height
+                ),
+            ));
+        }
+        self.frames += 1;
+        let pixels = self.pixels(screen);
+        match &mut self.output {
+            Output::Gif {
+                encoder,
+                pending,
+                written,
+            } => match pending {
+                // unchanged frames only make the last one stay longer
+                Some((last, count)) if *last == pixels && *count < MAX_MERGED_FRAMES => {
+                    *count += 1;
+                    Ok(())
+                }
+                _ => {
+                    let previous = pending.replace((pixels, 1));
+                    write_gif_frame(encoder, previous, written, self.width * self.scale)
+                }
+            },
+            Output::Y4m(writer) => {
+                writer.write_all(b"FRAME\n")?;
+                for plane in 0..3 {
+                    let yuv: Vec<u8> = self.palette.colors.iter().map(|c| yuv(*c)[plane]).collect();
+                    let plane: Vec<u8> = pixels.iter().map(|index| yuv[*index as usize]).collect();
+                    writer.write_all(&plane)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    // writes what is still pending, the GIF trailer follows when the
+    // recorder is dropped
+    pub fn finish(mut self) -> io::Result<()> {
+        match &mut self.output {
+            Output::Gif {
+                encoder,
+                pending,
+                written,
+            } => {
+                let width = self.width * self.scale;
+                write_gif_frame(encoder, pending.take(), written, width)?;
+            }
+            Output::Y4m(writer) => writer.flush()?,
+        }
+        Ok(())
+    }
+
+    // palette indices of the scaled screen, row by row
+    fn pixels(&self, screen: &ScreenBuffer) -> Vec<u8> {
+        let scale = self.scale as usize;
+        let width = self.width as usize * scale;
+        let height = self.height as usize * scale;
+        (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x / scale, y / scale)))
+            .map(|(x, y)| screen.get_pixel(x as u16, y as u16) as u8)
+            .collect()
+    }
+}
+
+// The delays are rounded so that they add up to the time the frames showed
+// at 60 Hz, alternating between 1 and 2 hundredths for single frames.
+fn write_gif_frame<W: Write>(
+    encoder: &mut gif::Encoder<W>,
+    frame: Option<(Vec<u8>, u32)>,
+    written: &mut u32,
+    width: u16,
+) -> io::Result<()> {
+    let (pixels, count) = match frame {
+        Some(frame) => frame,
+        None => return Ok(()),
+    };
+    let ticks =
+        |frames: u32| (frames * GIF_TICKS_PER_SECOND + FRAMES_PER_SECOND / 2) / FRAMES_PER_SECOND;
+    let delay = ticks(*written + count) - ticks(*written);
+    *written += count;
+
+    let height = (pixels.len() / width as usize) as u16;
+    let mut frame = gif::Frame::from_indexed_pixels(width, height, &pixels, None);
+    frame.delay = delay as u16;
+    encoder.write_frame(&frame)
+}
+
+// BT.601 in the limited range video expects
+fn yuv(Rgb(r, g, b): Rgb) -> [u8; 3] {
+    let (r, g, b) = (r as i32, g as i32, b as i32);
+    [
+        (((66 * r + 129 * g + 25 * b + 128) >> 8) + 16) as u8,
+        (((-38 * r - 74 * g + 112 * b + 128) >> 8) + 128) as u8,
+        (((112 * r - 94 * g - 18 * b + 128) >> 8) + 128) as u8,
+    ]
+}
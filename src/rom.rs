@@ -1,23 +1,156 @@
+use std::error::Error;
+use std::fmt;
 use std::fs::File;
+use std::io;
 use std::io::Read;
+use std::path::Path;
+
+use super::octo::{self, AssembleError, OctoOptions};
+use super::platform::Descriptor;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum RomFormat {
+    Binary,
+    OctoSource,
+    OctoCartridge,
+}
+
+impl RomFormat {
+    // decided by content alone, the file name doesn't matter
+    pub fn detect(data: &[u8]) -> RomFormat {
+        if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+            return RomFormat::OctoCartridge;
+        }
+        // CHIP-8 binaries practically always contain control bytes such as
+        // the 0x00 of 00E0 or the 0x12 of a jump; Octo source is plain text
+        // with labels, directives or assignments, all of which start with a
+        // colon, so printable binaries without one still load as binaries
+        let is_source = match std::str::from_utf8(data) {
+            Ok(text) => {
+                text.chars()
+                    .all(|c| !c.is_control() || c == '\n' || c == '\r' || c == '\t')
+                    && text.split_whitespace().any(|token| token.starts_with(':'))
+            }
+            Err(_) => false,
+        };
+        if is_source {
+            RomFormat::OctoSource
+        } else {
+            RomFormat::Binary
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum RomError {
+    Io(io::Error),
+    Cartridge(String),
+    Assemble(AssembleError),
+    TooLarge {
+        size: usize,
+        max: usize,
+        platform: &'static str,
+    },
+}
+
+impl fmt::Display for RomError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RomError::Io(e) => e.fmt(f),
+            RomError::Cartridge(message) => write!(f, "invalid Octo cartridge: {}", message),
+            RomError::Assemble(e) => write!(f, "could not assemble Octo source: {}", e),
+            RomError::TooLarge {
+                size,
+                max,
+                platform,
+            } => write!(
+                f,
+                "ROM is {} bytes, {} programs can be at most {} bytes",
+                size, platform, max
+            ),
+        }
+    }
+}
+
+impl Error for RomError {}
+
+impl From<io::Error> for RomError {
+    fn from(e: io::Error) -> Self {
+        RomError::Io(e)
+    }
+}
+
+impl From<AssembleError> for RomError {
+    fn from(e: AssembleError) -> Self {
+        RomError::Assemble(e)
+    }
+}
 
 pub struct Rom {
     raw: Vec<u8>,
+    options: Option<OctoOptions>,
 }
 
 impl Rom {
-    pub fn load(name: &str) -> Self {
-        let mut file = File::open(name).expect(&format!("Could not open ROM file at {:?}", name));
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, RomError> {
+        let mut file = File::open(path)?;
         let mut buf = Vec::new();
-        file.read_to_end(&mut buf).expect(&format!("Could not open ROM file at {:?}", name));
+        file.read_to_end(&mut buf)?;
+        Rom::from_bytes(buf)
+    }
+
+    pub fn from_bytes(data: Vec<u8>) -> Result<Self, RomError> {
+        let (raw, options) = match RomFormat::detect(&data) {
+            RomFormat::Binary => (data, None),
+            RomFormat::OctoSource => {
+                // detect only reports source for valid UTF-8
+                let source = String::from_utf8_lossy(&data);
+                (octo::assemble(&source)?, None)
+            }
+            RomFormat::OctoCartridge => {
+                let cartridge = octo::read_cartridge(&data).map_err(RomError::Cartridge)?;
+                (octo::assemble(&cartridge.program)?, Some(cartridge.options))
+            }
+        };
+        Ok(Rom { raw, options })
+    }
+
+    // a plain CHIP-8 binary, without looking at the contents
+    pub fn from_binary(data: Vec<u8>) -> Self {
         Rom {
-            raw: buf
+            raw: data,
+            options: None,
         }
     }
+
+    pub fn validate_for(&self, descriptor: &Descriptor) -> Result<(), RomError> {
+        let max = descriptor.max_rom_size();
+        if self.raw.len() > max {
+            return Err(RomError::TooLarge {
+                size: self.raw.len(),
+                max,
+                platform: descriptor.name,
+            });
+        }
+        Ok(())
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.raw
+    }
+
+    // options embedded in an Octo cartridge
+    pub fn options(&self) -> Option<&OctoOptions> {
+        self.options.as_ref()
+    }
+
+    pub fn sha1(&self) -> String {
+        sha1::Sha1::from(&self.raw).digest().to_string()
+    }
 }
 
-impl Into<Vec<u8>> for Rom {
-    fn into(self) -> Vec<u8> {
-        self.raw
+impl From<Rom> for Vec<u8> {
+    fn from(rom: Rom) -> Vec<u8> {
+        rom.raw
     }
 }
@@ -1,3 +1,4 @@
+#[derive(Debug, Clone, PartialEq)]
 pub struct ScreenBuffer {
     width: u16,
     height: u16,
@@ -8,8 +9,8 @@ impl ScreenBuffer {
     pub fn new(width: u16, height: u16) -> Self {
         let size = (width * height) as usize;
         ScreenBuffer {
-            width: width,
-            height: height,
+            width,
+            height,
             pixels: vec![false; size],
         }
     }
@@ -42,7 +43,7 @@ impl ScreenBuffer {
 
     fn index(&self, x: u16, y: u16) -> usize {
         let mut index: usize = (x * self.height() + y) as usize;
-        index = index % self.pixels.len();
+        index %= self.pixels.len();
         index
     }
 }
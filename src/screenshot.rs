@@ -0,0 +1,142 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+use super::palette::{Palette, Rgb};
+use super::screen_buffer::ScreenBuffer;
+
+// PNG for sharing, and the plain (ASCII) Netpbm formats whose output reads
+// and diffs well in tests: PBM with one bit per pixel, ignoring the palette,
+// and PGM with the brightness of the palette colours.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ImageFormat {
+    Png,
+    Pbm,
+    Pgm,
+}
+
+impl ImageFormat {
+    pub const NAMES: &'static [&'static str] = &["png", "pbm", "pgm"];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Pbm => "pbm",
+            ImageFormat::Pgm => "pgm",
+        }
+    }
+
+    // also the file extension
+    pub fn extension(self) -> &'static str {
+        self.name()
+    }
+}
+
+impl FromStr for ImageFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "png" => Ok(ImageFormat::Png),
+            "pbm" => Ok(ImageFormat::Pbm),
+            "pgm" => Ok(ImageFormat::Pgm),
+            _ => Err(format!(
+                "unknown image format {:?}, expected one of {}",
+                s,
+                ImageFormat::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ImageFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+// "<rom>-<frame>.<extension>", the frame zero-padded so the files sort in order
+pub fn file_name(rom: &str, frame: u32, extension: &str) -> String {
+    format!("{}-{:06}.{}", rom, frame, extension)
+}
+
+pub fn save<P: AsRef<Path>>(
+    path: P,
+    screen: &ScreenBuffer,
+    palette: &Palette,
+    scale: u16,
+    format: ImageFormat,
+) -> io::Result<()> {
+    fs::write(path, encode(screen, palette, scale, format))
+}
+
+// The screen with every pixel scale times scale pixels large.
+pub fn encode(
+    screen: &ScreenBuffer,
+    palette: &Palette,
+    scale: u16,
+    format: ImageFormat,
+) -> Vec<u8> {
+    let scale = scale.max(1) as u32;
+    let width = screen.width() as u32 * scale;
+    let height = screen.height() as u32 * scale;
+    let lit = |x: u32, y: u32| screen.get_pixel((x / scale) as u16, (y / scale) as u16);
+    let color = |x: u32, y: u32| match lit(x, y) {
+        true => palette.foreground(),
+        false => palette.background(),
+    };
+
+    match format {
+        ImageFormat::Pbm => netpbm("P1", width, height, None, |x, y| lit(x, y) as u8),
+        ImageFormat::Pgm => netpbm("P2", width, height, Some(255), |x, y| luma(color(x, y))),
+        ImageFormat::Png => {
+            let mut pixels = Vec::with_capacity((width * height * 3) as usize);
+            for y in 0..height {
+                for x in 0..width {
+                    let Rgb(r, g, b) = color(x, y);
+                    pixels.extend_from_slice(&[r, g, b]);
+                }
+            }
+            png(width, height, &pixels)
+        }
+    }
+}
+
+fn luma(Rgb(r, g, b): Rgb) -> u8 {
+    ((r as u32 * 299 + g as u32 * 587 + b as u32 * 114) / 1000) as u8
+}
+
+fn netpbm(
+    magic: &str,
+    width: u32,
+    height: u32,
+    max: Option<u8>,
+    value: impl Fn(u32, u32) -> u8,
+) -> Vec<u8> {
+    let mut out = format!("{}\n{} {}\n", magic, width, height);
+    if let Some(max) = max {
+        out += &format!("{}\n", max);
+    }
+    for y in 0..height {
+        let row: Vec<String> = (0..width).map(|x| value(x, y).to_string()).collect();
+        out += &row.join(" ");
+        out.push('\n');
+    }
+    out.into_bytes()
+}
+
+// An 8 bit RGB image.
+fn png(width: u32, height: u32, pixels: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut encoder = png::Encoder::new(&mut out, width, height);
+    encoder.set_color(png::ColorType::RGB);
+    encoder.set_depth(png::BitDepth::Eight);
+    // writing to memory only fails for a size that doesn't match the pixels
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(pixels))
+        .expect("screenshot pixels match the image size");
+    out
+}
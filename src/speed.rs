@@ -0,0 +1,228 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::str::FromStr;
+
+pub const HOST_FRAMES_PER_SECOND: u32 = 60;
+const MAX_MULTIPLE: u32 = 64;
+
+// How fast the machine runs compared to its 60 Hz, in frames per frame of
+// the host.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Speed {
+    Multiple(u32), // this many frames every host frame, 1 is normal speed
+    Fraction(u32), // one frame every this many host frames
+    Unlimited,     // as many frames as the host manages
+}
+
+impl Speed {
+    pub const NORMAL: Speed = Speed::Multiple(1);
+
+    // frames per host frame
+    fn rate(self) -> f64 {
+        match self {
+            Speed::Multiple(n) => n as f64,
+            Speed::Fraction(n) => 1.0 / n as f64,
+            Speed::Unlimited => f64::INFINITY,
+        }
+    }
+
+    // the speeds faster() and slower() step through
+    const STEPS: &'static [Speed] = &[
+        Speed::Fraction(8),
+        Speed::Fraction(4),
+        Speed::Fraction(2),
+        Speed::NORMAL,
+        Speed::Multiple(2),
+        Speed::Multiple(4),
+        Speed::Multiple(8),
+    ];
+}
+
+impl Default for Speed {
+    fn default() -> Self {
+        Speed::NORMAL
+    }
+}
+
+impl FromStr for Speed {
+    type Err = String;
+
+    // "4" for four times, "1/4" for a quarter of the speed, or "unlimited"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || {
+            format!(
+                "invalid speed {:?}, expected a factor from 1 to {} such as 4, a fraction such as 1/4, or unlimited",
+                s, MAX_MULTIPLE
+            )
+        };
+        let number = |n: &str| match n.parse::<u32>() {
+            Ok(n) if (1..=MAX_MULTIPLE).contains(&n) => Ok(n),
+            _ => Err(error()),
+        };
+        match s.split_once('/') {
+            _ if s == "unlimited" => Ok(Speed::Unlimited),
+            Some(("1", divisor)) => number(divisor).map(|n| match n {
+                1 => Speed::NORMAL,
+                n => Speed::Fraction(n),
+            }),
+            Some(_) => Err(error()),
+            None => number(s).map(Speed::Multiple),
+        }
+    }
+}
+
+impl fmt::Display for Speed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Speed::Multiple(n) => write!(f, "x{}", n),
+            Speed::Fraction(n) => write!(f, "x1/{}", n),
+            Speed::Unlimited => f.write_str("unlimited"),
+        }
+    }
+}
+
+// What to run in a frame of the host.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Pace {
+    Frames(u32),
+    Unlimited, // as many frames as fit in the time of a host frame
+}
+
+// Decides how many frames of the machine run in each frame of the host. The
+// host calls tick() at 60 Hz, so nothing here depends on a clock and every
+// decision can be replayed in tests.
+#[derive(Debug, Clone)]
+pub struct SpeedControl {
+    speed: Speed,
+    turbo_speed: Speed,
+    turbo: bool, // while the turbo key is held
+    paused: bool,
+    advance: u32, // frames to run while paused
+    ticks: u32,   // host frames since the last frame at a fractional speed
+}
+
+impl SpeedControl {
+    pub fn new(turbo_speed: Speed) -> Self {
+        SpeedControl {
+            speed: Speed::NORMAL,
+            turbo_speed,
+            turbo: false,
+            paused: false,
+            advance: 0,
+            ticks: 0,
+        }
+    }
+
+    // the speed in effect, turbo included
+    pub fn speed(&self) -> Speed {
+        match self.turbo {
+            true => self.turbo_speed,
+            false => self.speed,
+        }
+    }
+
+    pub fn set_speed(&mut self, speed: Speed) {
+        self.speed = speed;
+        self.ticks = 0;
+    }
+
+    // to the next of the steps either way, stopping at the ends
+    pub fn faster(&mut self) -> Speed {
+        let rate = self.speed.rate();
+        let speed = Speed::STEPS.iter().find(|speed| speed.rate() > rate);
+        self.set_speed(*speed.unwrap_or(&Speed::STEPS[Speed::STEPS.len() - 1]));
+        self.speed
+    }
+
+    pub fn slower(&mut self) -> Speed {
+        let rate = self.speed.rate();
+        let speed = Speed::STEPS.iter().rev().find(|speed| speed.rate() < rate);
+        self.set_speed(*speed.unwrap_or(&Speed::STEPS[0]));
+        self.speed
+    }
+
+    pub fn set_turbo(&mut self, turbo: bool) {
+        self.turbo = turbo;
+    }
+
+    pub fn turbo(&self) -> bool {
+        self.turbo
+    }
+
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+        self.advance = 0;
+    }
+
+    // pauses, and runs exactly one more frame with the next tick
+    pub fn advance(&mut self) {
+        self.paused = true;
+        self.advance += 1;
+    }
+
+    pub fn tick(&mut self) -> Pace {
+        if self.paused {
+            let frames = self.advance.min(1);
+            self.advance -= frames;
+            return Pace::Frames(frames);
+        }
+        match self.speed() {
+            Speed::Multiple(n) => Pace::Frames(n),
+            Speed::Unlimited => Pace::Unlimited,
+            Speed::Fraction(n) => {
+                self.ticks += 1;
+                if self.ticks >= n {
+                    self.ticks = 0;
+                    Pace::Frames(1)
+                } else {
+                    Pace::Frames(0)
+                }
+            }
+        }
+    }
+}
+
+// Frames and instructions run per second, averaged over the last second of
+// host frames.
+#[derive(Debug, Clone, Default)]
+pub struct RateMeter {
+    ticks: VecDeque<(u32, u32)>, // frames and instructions of each host frame, newest last
+}
+
+impl RateMeter {
+    pub fn new() -> Self {
+        RateMeter::default()
+    }
+
+    // what ran in a host frame
+    pub fn record(&mut self, frames: u32, instructions: u32) {
+        if self.ticks.len() == HOST_FRAMES_PER_SECOND as usize {
+            self.ticks.pop_front();
+        }
+        self.ticks.push_back((frames, instructions));
+    }
+
+    pub fn clear(&mut self) {
+        self.ticks.clear();
+    }
+
+    pub fn fps(&self) -> u32 {
+        self.per_second(|(frames, _)| frames as u64)
+    }
+
+    pub fn ips(&self) -> u32 {
+        self.per_second(|(_, instructions)| instructions as u64)
+    }
+
+    fn per_second(&self, count: impl Fn((u32, u32)) -> u64) -> u32 {
+        if self.ticks.is_empty() {
+            return 0;
+        }
+        let total: u64 = self.ticks.iter().map(|tick| count(*tick)).sum();
+        (total * HOST_FRAMES_PER_SECOND as u64 / self.ticks.len() as u64) as u32
+    }
+}
@@ -0,0 +1,95 @@
+// The call stack. Real interpreters kept it in a fixed area of memory, so
+// how deep it is and what happens when a program goes past either end
+// depends on the platform; see Quirks::stack_depth and Quirks::stack_wraps.
+
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Frame {
+    pub call_site: u16,   // address of the CALL instruction
+    pub return_addr: u16, // where RET continues
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum StackError {
+    Overflow,
+    Underflow,
+}
+
+#[derive(Debug, Clone)]
+pub struct Stack {
+    entries: Vec<Frame>, // may hold stale entries above sp
+    sp: usize,
+    depth: Option<usize>, // None for unlimited
+    wraps: bool,          // go around at either end instead of failing
+}
+
+impl Stack {
+    pub fn new(depth: Option<usize>, wraps: bool) -> Self {
+        Stack {
+            entries: Vec::new(),
+            sp: 0,
+            depth,
+            wraps,
+        }
+    }
+
+    // frames beyond a smaller depth are dropped
+    pub fn set_depth(&mut self, depth: Option<usize>, wraps: bool) {
+        if let Some(depth) = depth {
+            self.entries.truncate(depth);
+            self.sp = self.sp.min(depth);
+        }
+        self.depth = depth;
+        self.wraps = wraps;
+    }
+
+    pub fn depth(&self) -> Option<usize> {
+        self.depth
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.sp = 0;
+    }
+
+    pub fn push(&mut self, frame: Frame) -> Result<(), StackError> {
+        if self.depth == Some(self.sp) {
+            if !self.wraps {
+                return Err(StackError::Overflow);
+            }
+            self.sp = 0;
+        }
+        match self.entries.get_mut(self.sp) {
+            Some(entry) => *entry = frame,
+            None => self.entries.push(frame),
+        }
+        self.sp += 1;
+        Ok(())
+    }
+
+    pub fn pop(&mut self) -> Result<Frame, StackError> {
+        if self.sp == 0 {
+            match self.depth {
+                Some(depth) if self.wraps => self.sp = depth,
+                _ => return Err(StackError::Underflow),
+            }
+        }
+        self.sp -= 1;
+        // entries that were never written read as zero, like cleared memory
+        Ok(self.entries.get(self.sp).copied().unwrap_or_default())
+    }
+
+    // the active frames, innermost last
+    pub fn frames(&self) -> &[Frame] {
+        &self.entries[..self.sp.min(self.entries.len())]
+    }
+
+    // for save states: every entry including the stale ones, and the pointer
+    pub(crate) fn entries(&self) -> (&[Frame], usize) {
+        (&self.entries, self.sp)
+    }
+
+    pub(crate) fn restore(&mut self, entries: Vec<Frame>, sp: usize) {
+        self.entries = entries;
+        self.sp = sp;
+    }
+}
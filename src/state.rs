@@ -0,0 +1,139 @@
+use std::error::Error;
+use std::fmt;
+
+// Save states start with a magic number and a format version, followed by the
+// fields of the machine in a fixed order; multi-byte values are big endian.
+pub const MAGIC: &[u8; 4] = b"C8ST";
+pub const VERSION: u8 = 3;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StateError {
+    BadMagic,
+    UnsupportedVersion(u8),
+    Truncated,
+    TrailingData(usize),   // number of bytes left over
+    Invalid(&'static str), // a field is out of range
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StateError::BadMagic => write!(f, "not a CHIP-8 save state"),
+            StateError::UnsupportedVersion(version) => {
+                write!(f, "unsupported save state version {}", version)
+            }
+            StateError::Truncated => write!(f, "save state is truncated"),
+            StateError::TrailingData(len) => {
+                write!(f, "save state has {} unexpected trailing bytes", len)
+            }
+            StateError::Invalid(field) => write!(f, "save state has an invalid {}", field),
+        }
+    }
+}
+
+impl Error for StateError {}
+
+pub(crate) struct Writer {
+    data: Vec<u8>,
+}
+
+impl Writer {
+    pub fn new() -> Self {
+        let mut data = MAGIC.to_vec();
+        data.push(VERSION);
+        Writer { data }
+    }
+
+    pub fn u8(&mut self, value: u8) {
+        self.data.push(value);
+    }
+
+    pub fn u16(&mut self, value: u16) {
+        self.data.extend_from_slice(&value.to_be_bytes());
+    }
+
+    pub fn u32(&mut self, value: u32) {
+        self.data.extend_from_slice(&value.to_be_bytes());
+    }
+
+    pub fn bytes(&mut self, bytes: &[u8]) {
+        self.data.extend_from_slice(bytes);
+    }
+
+    // eight pixels per byte, the leftmost one in the highest bit
+    pub fn bits(&mut self, bits: impl Iterator<Item = bool>) {
+        let mut byte = 0;
+        let mut count = 0;
+        for bit in bits {
+            byte = byte << 1 | bit as u8;
+            count += 1;
+            if count == 8 {
+                self.data.push(byte);
+                byte = 0;
+                count = 0;
+            }
+        }
+        if count > 0 {
+            self.data.push(byte << (8 - count));
+        }
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        self.data
+    }
+}
+
+pub(crate) struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(data: &'a [u8]) -> Result<Self, StateError> {
+        let mut reader = Reader { data };
+        let magic = reader.bytes(MAGIC.len()).map_err(|_| StateError::BadMagic)?;
+        if magic != MAGIC {
+            return Err(StateError::BadMagic);
+        }
+        match reader.u8()? {
+            VERSION => Ok(reader),
+            version => Err(StateError::UnsupportedVersion(version)),
+        }
+    }
+
+    pub fn u8(&mut self) -> Result<u8, StateError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    pub fn u16(&mut self) -> Result<u16, StateError> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    pub fn u32(&mut self) -> Result<u32, StateError> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    pub fn bytes(&mut self, len: usize) -> Result<&'a [u8], StateError> {
+        if self.data.len() < len {
+            return Err(StateError::Truncated);
+        }
+        let (bytes, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(bytes)
+    }
+
+    pub fn bits(&mut self, len: usize) -> Result<Vec<bool>, StateError> {
+        let bytes = self.bytes(len.div_ceil(8))?;
+        Ok((0..len)
+            .map(|i| bytes[i / 8] & (0x80 >> (i % 8)) != 0)
+            .collect())
+    }
+
+    pub fn finish(self) -> Result<(), StateError> {
+        match self.data.len() {
+            0 => Ok(()),
+            len => Err(StateError::TrailingData(len)),
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use super::opcode::OpCode;
+
+// How many instructions run between two 60 Hz frames. The COSMAC VIP runs
+// its 1.76 MHz CDP1802 at 8 clocks per machine cycle, and the display
+// interrupt takes a large part of every frame for the DMA transfer of the
+// screen, which is why CHIP-8 programs ran much slower than a fixed rate of
+// instructions suggests.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Timing {
+    Fixed(u32), // this many instructions per frame, whatever they are
+    Vip,        // charge each instruction its machine cycles on the VIP
+}
+
+impl Default for Timing {
+    fn default() -> Self {
+        Timing::Fixed(3)
+    }
+}
+
+// The CDP1861 video chip paces the VIP: a frame is 262 lines of 14 machine
+// cycles, and each of the 128 lines shown takes 8 cycles of DMA (RCA CDP1861
+// datasheet). 3668 cycles at 1_760_640 Hz / 8 clocks per cycle make 60 Hz.
+pub const VIP_FRAME_CYCLES: u32 = 262 * 14;
+pub const VIP_INTERRUPT_CYCLES: u32 = 128 * 8 + 48; // DMA of the display and the timer routine
+pub const VIP_FETCH_CYCLES: u32 = 40; // fetching and dispatching, common to all instructions
+pub const VIP_SKIP_CYCLES: u32 = 4; // the two INC of the PC when a skip is taken
+
+// Machine cycles the VIP interpreter takes for an instruction, fetching
+// included, given the registers it started with and whether it skipped. The
+// costs are those of the interpreter routines as taken apart in Laurence
+// Scotford's "Chip-8 on the COSMAC VIP" articles (laurencescotford.net),
+// following the usual path through them. DRAW, BCD and the register
+// transfers loop depending on their operands, and skips taken run two more
+// instructions than skips that aren't.
+pub fn vip_cycles(opcode: OpCode, regs: &[u8; 16], skipped: bool) -> u32 {
+    let skip = if skipped { VIP_SKIP_CYCLES } else { 0 };
+    VIP_FETCH_CYCLES
+        + match opcode {
+            OpCode::SYS { .. } => 0,
+            OpCode::CLR => 3034, // clears the 256 bytes of the display buffer
+            OpCode::RET => 10,
+            OpCode::JUMP { .. } => 12,
+            OpCode::CALL { .. } => 26,
+            OpCode::SKE { .. } | OpCode::SKNE { .. } => 10 + skip,
+            OpCode::SKRE { .. } | OpCode::SKRNE { .. } => 14 + skip,
+            OpCode::LOAD { .. } => 6,
+            OpCode::ADD { .. } => 10,
+            OpCode::MOVE { .. } => 12,
+            OpCode::OR { .. }
+            | OpCode::AND { .. }
+            | OpCode::XOR { .. }
+            | OpCode::ADDR { .. }
+            | OpCode::SUB { .. }
+            | OpCode::SHR { .. }
+            | OpCode::SUBN { .. }
+            | OpCode::SHL { .. } => 44,
+            OpCode::LOADI { .. } => 12,
+            OpCode::JUMPI { .. } => 22,
+            OpCode::RAND { .. } => 36,
+            OpCode::DRAW { s, n, .. } => draw_cycles(regs[s as usize & 0xF], n),
+            OpCode::SKP { .. } | OpCode::SKNP { .. } => 14 + skip,
+            OpCode::MOVED { .. } => 10,
+            OpCode::KEYD { .. } => 18, // for every time it polls the keypad
+            OpCode::LOADD { .. } | OpCode::LOADS { .. } => 10,
+            OpCode::ADDI { .. } => 16,
+            OpCode::LDSPR { .. } => 20,
+            OpCode::BCD { s } => bcd_cycles(regs[s as usize & 0xF]),
+            OpCode::STOR { s } | OpCode::READ { s } => 14 + 14 * (s as u32 + 1),
+            OpCode::Unknown(_) => 0,
+        }
+}
+
+// Each sprite row is shifted into place bit by bit, so rows that do not start
+// on a byte boundary cost extra and touch two display bytes.
+fn draw_cycles(x: u8, rows: u8) -> u32 {
+    let shift = (x % 8) as u32;
+    let per_row = if shift == 0 { 34 } else { 68 + 6 * shift };
+    26 + rows as u32 * per_row
+}
+
+// The digits are found by repeated subtraction of 100, 10 and 1.
+fn bcd_cycles(value: u8) -> u32 {
+    let digits = value / 100 + value / 10 % 10 + value % 10;
+    84 + 16 * digits as u32
+}
@@ -0,0 +1,50 @@
+// Where the screen goes in a window of any size: scaled up by the largest
+// whole factor that fits and centred, with the rest of the window left as a
+// border. Whole factors keep all CHIP-8 pixels the same size. A window
+// smaller than the screen shows it unscaled and cut off.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Viewport {
+    pub x: u32, // top left corner in the window
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub scale: u32, // window pixels per CHIP-8 pixel
+}
+
+impl Viewport {
+    pub fn fit(
+        window_width: u32,
+        window_height: u32,
+        screen_width: u16,
+        screen_height: u16,
+    ) -> Self {
+        let screen_width = (screen_width as u32).max(1);
+        let screen_height = (screen_height as u32).max(1);
+        let scale = (window_width / screen_width)
+            .min(window_height / screen_height)
+            .max(1);
+        let width = screen_width * scale;
+        let height = screen_height * scale;
+        Viewport {
+            x: window_width.saturating_sub(width) / 2,
+            y: window_height.saturating_sub(height) / 2,
+            width,
+            height,
+            scale,
+        }
+    }
+
+    // Lines one window pixel wide between the CHIP-8 pixels, as x, y, width
+    // and height. They are only worth drawing at larger scales, below that
+    // there would be more grid than picture.
+    pub fn grid_lines(&self, min_scale: u32) -> Vec<(u32, u32, u32, u32)> {
+        if self.scale < min_scale {
+            return Vec::new();
+        }
+        let columns = (1..self.width / self.scale)
+            .map(|column| (self.x + column * self.scale, self.y, 1, self.height));
+        let rows = (1..self.height / self.scale)
+            .map(|row| (self.x, self.y + row * self.scale, self.width, 1));
+        columns.chain(rows).collect()
+    }
+}
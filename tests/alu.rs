@@ -0,0 +1,158 @@
+// Properties of the arithmetic instructions over all register values, checked
+// under every quirk preset. Each case runs a tiny program that loads the
+// operands and executes the instruction under test.
+
+use chip_8::chip8::{Chip8, Snapshot};
+use chip_8::fault::Fault;
+use chip_8::opcode::{self, OpCode};
+use chip_8::quirks::{QuirkPreset, Quirks};
+use chip_8::rom::Rom;
+use proptest::prelude::*;
+
+const PRESETS: [QuirkPreset; 3] = [QuirkPreset::Chip8, QuirkPreset::Vip, QuirkPreset::Schip];
+const VF: usize = 0xF;
+
+fn run(quirks: Quirks, program: &[u16]) -> Snapshot {
+    let mut chip8 = Chip8::new();
+    chip8.set_quirks(quirks);
+    // the whole program then runs as one compiled block
+    #[cfg(feature = "dynarec")]
+    chip8.set_dynarec(true);
+    chip8.init();
+    let rom = program
+        .iter()
+        .flat_map(|op| op.to_be_bytes().to_vec())
+        .collect();
+    chip8.load_rom(Rom::from_binary(rom)).unwrap();
+    chip8.run_cycles(program.len() as u32);
+    assert_eq!(chip8.fault(), None);
+    chip8.snapshot()
+}
+
+// Vx = a, Vy = b, then the 8xyN instruction
+fn alu(quirks: Quirks, n: u16, x: usize, y: usize, a: u8, b: u8) -> Snapshot {
+    let (x, y) = (x as u16, y as u16);
+    run(
+        quirks,
+        &[
+            0x6000 | x << 8 | a as u16,
+            0x6000 | y << 8 | b as u16,
+            0x8000 | x << 8 | y << 4 | n,
+        ],
+    )
+}
+
+// two different registers other than VF
+fn operands() -> impl Strategy<Value = (usize, usize)> {
+    (0..VF, 0..VF).prop_filter("x and y must differ", |(x, y)| x != y)
+}
+
+proptest! {
+    #[test]
+    fn addr_splits_the_nine_bit_sum((x, y) in operands(), a: u8, b: u8) {
+        for preset in &PRESETS {
+            let state = alu(Quirks::from_preset(*preset), 0x4, x, y, a, b);
+            let sum = a as u16 + b as u16;
+            prop_assert_eq!(state.regs[x], sum as u8, "{}", preset);
+            prop_assert_eq!(state.regs[VF] as u16, sum >> 8, "{}", preset);
+        }
+    }
+
+    #[test]
+    fn sub_sets_vf_to_not_borrow((x, y) in operands(), a: u8, b: u8) {
+        for preset in &PRESETS {
+            let state = alu(Quirks::from_preset(*preset), 0x5, x, y, a, b);
+            prop_assert_eq!(state.regs[x], a.wrapping_sub(b), "{}", preset);
+            prop_assert_eq!(state.regs[VF], (a >= b) as u8, "{}", preset);
+        }
+    }
+
+    #[test]
+    fn subn_sets_vf_to_not_borrow((x, y) in operands(), a: u8, b: u8) {
+        for preset in &PRESETS {
+            let state = alu(Quirks::from_preset(*preset), 0x7, x, y, a, b);
+            prop_assert_eq!(state.regs[x], b.wrapping_sub(a), "{}", preset);
+            prop_assert_eq!(state.regs[VF], (b >= a) as u8, "{}", preset);
+        }
+    }
+
+    // the bit shifted out ends up in VF, so shifting back restores the source
+    #[test]
+    fn shr_round_trips((x, y) in operands(), a: u8, b: u8) {
+        for preset in &PRESETS {
+            let quirks = Quirks::from_preset(*preset);
+            let state = alu(quirks, 0x6, x, y, a, b);
+            let source = if quirks.shift_uses_vy { b } else { a };
+            prop_assert_eq!(state.regs[x] << 1 | state.regs[VF], source, "{}", preset);
+            prop_assert!(state.regs[VF] <= 1, "{}", preset);
+        }
+    }
+
+    #[test]
+    fn shl_round_trips((x, y) in operands(), a: u8, b: u8) {
+        for preset in &PRESETS {
+            let quirks = Quirks::from_preset(*preset);
+            let state = alu(quirks, 0xE, x, y, a, b);
+            let source = if quirks.shift_uses_vy { b } else { a };
+            prop_assert_eq!(state.regs[x] >> 1 | state.regs[VF] << 7, source, "{}", preset);
+            prop_assert!(state.regs[VF] <= 1, "{}", preset);
+        }
+    }
+
+    // the flag is written after the result, so it is what remains in VF
+    #[test]
+    fn flag_wins_when_vf_is_the_destination(y in 0..VF, a: u8, b: u8) {
+        for preset in &PRESETS {
+            let quirks = Quirks::from_preset(*preset);
+            let source = if quirks.shift_uses_vy { b } else { a };
+            let flags = [
+                (0x4, a.overflowing_add(b).1 as u8),
+                (0x5, (a >= b) as u8),
+                (0x6, source & 1),
+                (0x7, (b >= a) as u8),
+                (0xE, source >> 7),
+            ];
+            for (n, flag) in flags {
+                let state = alu(quirks, n, VF, y, a, b);
+                prop_assert_eq!(state.regs[VF], flag, "{} 8F{:X}{:X}", preset, y, n);
+            }
+        }
+    }
+
+    // the digits are read back into V0-V2 to check them
+    #[test]
+    fn bcd_reconstructs_the_byte(x in 3..VF, value: u8) {
+        for preset in &PRESETS {
+            let x = x as u16;
+            let state = run(
+                Quirks::from_preset(*preset),
+                &[0xA300, 0x6000 | x << 8 | value as u16, 0xF033 | x << 8, 0xF265],
+            );
+            let digits = &state.regs[..3];
+            prop_assert!(digits.iter().all(|digit| *digit < 10), "{}", preset);
+            let number = digits[0] as u16 * 100 + digits[1] as u16 * 10 + digits[2] as u16;
+            prop_assert_eq!(number, value as u16, "{}", preset);
+        }
+    }
+}
+
+#[test]
+fn undocumented_8xyn_forms_are_invalid() {
+    for n in [0x8, 0x9, 0xA, 0xB, 0xC, 0xD, 0xF] {
+        let raw = 0x8120 | n;
+        assert_eq!(opcode::decode(raw), OpCode::Unknown(raw));
+
+        let mut chip8 = Chip8::new();
+        #[cfg(feature = "dynarec")]
+        chip8.set_dynarec(true);
+        chip8.init();
+        chip8
+            .load_rom(Rom::from_binary(raw.to_be_bytes().to_vec()))
+            .unwrap();
+        chip8.run_cycles(1);
+        assert_eq!(
+            chip8.fault(),
+            Some(Fault::InvalidOpcode { addr: 0x200, raw })
+        );
+    }
+}
@@ -0,0 +1,174 @@
+use chip_8::analysis::{self, Extension, Finding, Report};
+use chip_8::platform::Descriptor;
+
+// the ROM from 16 bit instructions
+fn rom(instructions: &[u16]) -> Vec<u8> {
+    instructions.iter().flat_map(|i| i.to_be_bytes()).collect()
+}
+
+fn analyze(rom: &[u8]) -> Report {
+    analysis::analyze(rom, Descriptor::find("chip8").unwrap())
+}
+
+#[test]
+fn follows_calls_and_both_sides_of_skips() {
+    let report = analyze(&rom(&[
+        0x2206, // 0x200: CALL 0x206
+        0x3000, // 0x202: SE V0, 0
+        0x1208, // 0x204: JP 0x208
+        0x00EE, // 0x206: RET
+        0x1208, // 0x208: JP 0x208
+    ]));
+    let reachable: Vec<u16> = report.reachable.iter().copied().collect();
+    assert_eq!(reachable, vec![0x200, 0x202, 0x204, 0x206, 0x208]);
+    assert!(report.findings.is_empty());
+    assert_eq!(report.suggested_platform.unwrap().name, "chip8");
+}
+
+#[test]
+fn reports_what_cannot_be_followed() {
+    let report = analyze(&rom(&[
+        0x3000, // 0x200: SE V0, 0
+        0x1300, // 0x202: JP 0x300
+        0xB200, // 0x204: JP V0, 0x200
+    ]));
+    assert_eq!(
+        report.findings,
+        vec![
+            Finding::JumpOutsideRom {
+                at: 0x202,
+                target: 0x300
+            },
+            Finding::UnresolvedJump { at: 0x204 },
+        ]
+    );
+
+    let report = analyze(&rom(&[0x6000, 0x8008]));
+    assert_eq!(
+        report.findings,
+        vec![Finding::UnknownOpcode {
+            at: 0x202,
+            raw: 0x8008
+        }]
+    );
+
+    // half an instruction at the end
+    let report = analyze(&[0x60, 0x00, 0x12]);
+    assert_eq!(report.findings, vec![Finding::RunsOffEnd { at: 0x202 }]);
+}
+
+#[test]
+fn stores_into_reachable_code_are_self_modifying() {
+    let report = analyze(&rom(&[
+        0xA206, // 0x200: LD I, 0x206
+        0xF155, // 0x202: LD [I], V1
+        0x1204, // 0x204: JP 0x204
+        0x1206, // 0x206: JP 0x206, never reached
+    ]));
+    assert!(report.findings.is_empty());
+
+    let report = analyze(&rom(&[
+        0xA204, // 0x200: LD I, 0x204
+        0xF033, // 0x202: LD B, V0
+        0x1204, // 0x204: JP 0x204
+    ]));
+    assert_eq!(
+        report.findings,
+        vec![Finding::SelfModifying {
+            at: 0x202,
+            target: 0x204
+        }]
+    );
+}
+
+#[test]
+fn extensions_suggest_a_platform() {
+    let report = analyze(&rom(&[0x00FF, 0x00FD]));
+    assert!(report.uses(Extension::SuperChip));
+    assert_eq!(report.suggested_platform.unwrap().name, "schip");
+
+    // the four byte i := long skips its operand
+    let report = analyze(&rom(&[0xF000, 0xFFFF, 0x1204]));
+    assert!(report.uses(Extension::XoChip));
+    assert!(!report.reachable.contains(&0x202));
+    assert!(report.reachable.contains(&0x204));
+    assert_eq!(report.suggested_platform, None);
+    assert!(report
+        .to_string()
+        .contains("Suggested platform: XO-CHIP, which is not supported"));
+}
+
+#[test]
+fn stops_at_the_end_of_the_address_space() {
+    // LD V0, 0 up to the last address of the 16 bit address space
+    let mut instructions = vec![0x6000; (0x10000 - 0x200) / 2];
+    let too_large = Finding::TooLarge {
+        size: 0xFE00,
+        max: 0xE00,
+        platform: "chip8",
+    };
+    let report = analyze(&rom(&instructions));
+    assert_eq!(
+        report.findings,
+        vec![too_large.clone(), Finding::RunsOffEnd { at: 0xFFFE }]
+    );
+
+    *instructions.last_mut().unwrap() = 0x3000; // SE V0, 0
+    let report = analyze(&rom(&instructions));
+    assert_eq!(
+        report.findings,
+        vec![too_large, Finding::RunsOffEnd { at: 0xFFFE }]
+    );
+
+    *instructions.last_mut().unwrap() = 0xF000; // i := long without its operand
+    let report = analyze(&rom(&instructions));
+    assert!(report
+        .findings
+        .contains(&Finding::RunsOffEnd { at: 0xFFFE }));
+}
+
+#[test]
+fn large_roms_are_reported_not_moved_to_another_platform() {
+    let vip = Descriptor::find("vip").unwrap();
+    let mut instructions = vec![0x6000; 0xCA0 / 2];
+    let report = analysis::analyze(&rom(&instructions), vip);
+    assert_eq!(
+        report.findings,
+        vec![Finding::JumpOutsideRom {
+            at: 0xE9E,
+            target: 0xEA0
+        }]
+    );
+
+    instructions.push(0x6000);
+    let report = analysis::analyze(&rom(&instructions), vip);
+    assert_eq!(
+        report.findings[0],
+        Finding::TooLarge {
+            size: 0xCA2,
+            max: 0xCA0,
+            platform: "vip"
+        }
+    );
+    assert_eq!(report.suggested_platform.unwrap().name, "vip");
+    assert!(report
+        .to_string()
+        .contains("ROM is 3234 bytes, vip programs can be at most 3232 bytes"));
+}
+
+#[test]
+fn analysis_starts_at_the_load_address() {
+    let eti660 = Descriptor::find("eti660").unwrap();
+    let report = analysis::analyze(
+        &rom(&[
+            0x2604, // 0x600: CALL 0x604
+            0x1602, // 0x602: JP 0x602
+            0x00EE, // 0x604: RET
+        ]),
+        eti660,
+    );
+    let reachable: Vec<u16> = report.reachable.iter().copied().collect();
+    assert_eq!(reachable, vec![0x600, 0x602, 0x604]);
+    assert!(report.findings.is_empty());
+    assert_eq!(report.suggested_platform.unwrap().name, "eti660");
+}
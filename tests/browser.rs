@@ -0,0 +1,87 @@
+use std::fs;
+
+use chip_8::browser::RomBrowser;
+use chip_8::database::Database;
+
+#[test]
+fn scans_the_files_of_a_directory_by_name() {
+    let dir = std::env::temp_dir().join(format!("chip_8-browser-{}", std::process::id()));
+    fs::create_dir_all(dir.join("subdir")).unwrap();
+    fs::copy("roms/BRIX", dir.join("brix.ch8")).unwrap();
+    fs::write(dir.join("Zigzag"), [0x12, 0x00]).unwrap();
+    // too large to be looked up, even if the start is a known ROM
+    let mut large = fs::read("roms/BRIX").unwrap();
+    large.resize(0x10001, 0);
+    fs::write(dir.join("large.ch8"), &large).unwrap();
+
+    let browser = RomBrowser::scan(&dir, &Database::bundled()).unwrap();
+    assert_eq!(browser.dir(), dir.as_path());
+    let entries: Vec<_> = browser
+        .entries()
+        .iter()
+        .map(|e| {
+            (
+                e.name.as_str(),
+                e.size,
+                e.info.as_ref().map(|i| &i.title[..]),
+            )
+        })
+        .collect();
+    // directories are left out and upper case sorts first
+    assert_eq!(
+        entries,
+        vec![
+            ("Zigzag", 2, None),
+            ("brix.ch8", 280, Some("Brix")),
+            ("large.ch8", 0x10001, None),
+        ]
+    );
+    assert_eq!(browser.entries()[1].path, dir.join("brix.ch8"));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn the_selection_stays_on_the_list() {
+    let dir = std::env::temp_dir().join(format!("chip_8-selection-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    for name in &["a", "b", "c"] {
+        fs::write(dir.join(name), [0x12, 0x00]).unwrap();
+    }
+    let database = Database::bundled();
+
+    let mut browser = RomBrowser::scan(&dir, &database).unwrap();
+    let selected = |browser: &RomBrowser| browser.selected().unwrap().name.clone();
+    assert_eq!(selected(&browser), "a");
+    browser.select_previous();
+    assert_eq!(selected(&browser), "a");
+    browser.select_next();
+    browser.select_next();
+    browser.select_next();
+    assert_eq!(selected(&browser), "c");
+    browser.select_first();
+    assert_eq!(browser.selected_index(), 0);
+    browser.select_last();
+    assert_eq!(browser.selected_index(), 2);
+
+    // unknown paths keep the selection
+    browser.select_path(&dir.join("b"));
+    assert_eq!(selected(&browser), "b");
+    browser.select_path(&dir.join("d"));
+    assert_eq!(selected(&browser), "b");
+
+    browser.set_message("could not load b".to_string());
+    assert_eq!(browser.message(), Some("could not load b"));
+    browser.clear_message();
+    assert_eq!(browser.message(), None);
+
+    // an empty directory has nothing to select
+    fs::remove_dir_all(&dir).unwrap();
+    fs::create_dir_all(&dir).unwrap();
+    let mut browser = RomBrowser::scan(&dir, &database).unwrap();
+    browser.select_next();
+    browser.select_last();
+    assert!(browser.selected().is_none());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
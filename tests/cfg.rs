@@ -0,0 +1,121 @@
+use chip_8::cfg::{self, EdgeKind, GraphFormat, Target};
+
+fn rom(instructions: &[u16]) -> Vec<u8> {
+    instructions.iter().flat_map(|i| i.to_be_bytes()).collect()
+}
+
+// (from, to, kind) of every edge
+fn edges(graph: &cfg::Cfg) -> Vec<(u16, Target, EdgeKind)> {
+    graph
+        .edges
+        .iter()
+        .map(|edge| (edge.from, edge.to, edge.kind))
+        .collect()
+}
+
+#[test]
+fn skips_have_an_edge_for_either_outcome() {
+    let graph = cfg::build(&rom(&[
+        0x6001, // 0x200: LD V0, 1
+        0x3001, // 0x202: SE V0, 1
+        0x6002, // 0x204: LD V0, 2
+        0x1206, // 0x206: JP 0x206
+    ]));
+    let starts: Vec<u16> = graph.blocks.iter().map(|block| block.start).collect();
+    assert_eq!(starts, vec![0x200, 0x204, 0x206]);
+    assert_eq!(graph.blocks[0].instructions.len(), 2);
+    assert_eq!(
+        edges(&graph),
+        vec![
+            (0x200, Target::Block(0x204), EdgeKind::SkipNotTaken),
+            (0x200, Target::Block(0x206), EdgeKind::SkipTaken),
+            (0x204, Target::Block(0x206), EdgeKind::Fallthrough),
+            (0x206, Target::Block(0x206), EdgeKind::Jump),
+        ]
+    );
+}
+
+#[test]
+fn calls_build_the_call_graph() {
+    let graph = cfg::build(&rom(&[
+        0x2206, // 0x200: CALL 0x206
+        0x1202, // 0x202: JP 0x202
+        0x0000, // 0x204: data
+        0x220A, // 0x206: CALL 0x20A
+        0x00EE, // 0x208: RET
+        0x00EE, // 0x20A: RET
+    ]));
+    let calls: Vec<(u16, Vec<u16>)> = graph
+        .subroutines
+        .iter()
+        .map(|sub| (sub.entry, sub.calls.iter().copied().collect()))
+        .collect();
+    assert_eq!(
+        calls,
+        vec![(0x200, vec![0x206]), (0x206, vec![0x20A]), (0x20A, vec![])]
+    );
+    assert!(edges(&graph).contains(&(0x200, Target::Block(0x202), EdgeKind::CallReturn)));
+}
+
+#[test]
+fn jumps_through_a_register_are_unresolved() {
+    // JP V0, 0x300
+    let graph = cfg::build(&rom(&[0xB300]));
+    assert_eq!(
+        edges(&graph),
+        vec![(0x200, Target::Unresolved, EdgeKind::Unresolved)]
+    );
+    let dot = graph.export(GraphFormat::Dot);
+    assert!(dot.contains("block_200 -> unresolved [style=dashed color=gray];"));
+    assert!(dot.contains("unresolved [label=\"?\" shape=diamond];"));
+}
+
+#[test]
+fn targets_outside_the_rom_are_kept() {
+    let graph = cfg::build(&rom(&[
+        0x3000, // 0x200: SE V0, 0
+        0x1300, // 0x202: JP 0x300
+    ]));
+    assert_eq!(
+        edges(&graph),
+        vec![
+            (0x200, Target::Block(0x202), EdgeKind::SkipNotTaken),
+            (0x200, Target::OutsideRom(0x204), EdgeKind::SkipTaken),
+            (0x202, Target::OutsideRom(0x300), EdgeKind::Jump),
+        ]
+    );
+    let dot = graph.export(GraphFormat::Dot);
+    assert!(dot.contains("block_202 -> outside_300 [color=blue];"));
+    assert!(dot.contains("outside_300 [label=\"0x300\" shape=octagon];"));
+}
+
+#[test]
+fn the_end_of_the_address_space_is_outside() {
+    // SE V0, 0 as the last instruction of 64 KiB of memory
+    let mut instructions = vec![0x6000; (0x10000 - 0x200) / 2];
+    *instructions.last_mut().unwrap() = 0x3000;
+    let graph = cfg::build(&rom(&instructions));
+    assert_eq!(graph.blocks.len(), 1);
+    assert_eq!(
+        edges(&graph),
+        vec![
+            (0x200, Target::OutsideRom(0x10000), EdgeKind::SkipNotTaken),
+            (0x200, Target::OutsideRom(0x10002), EdgeKind::SkipTaken),
+        ]
+    );
+}
+
+#[test]
+fn exports_json() {
+    let graph = cfg::build(&rom(&[0x6005, 0xB300]));
+    let json: serde_json::Value = serde_json::from_str(&graph.export(GraphFormat::Json)).unwrap();
+    assert_eq!(json["blocks"][0]["start"], 0x200);
+    assert_eq!(json["blocks"][0]["instructions"][0]["raw"], 0x6005);
+    assert_eq!(json["edges"][0]["to"], "unresolved");
+    assert_eq!(json["edges"][0]["kind"], "unresolved");
+    assert_eq!(json["subroutines"][0]["entry"], 0x200);
+
+    let json: serde_json::Value =
+        serde_json::from_str(&cfg::build(&rom(&[0x1300])).to_json()).unwrap();
+    assert_eq!(json["edges"][0]["to"]["outside_rom"], 0x300);
+}
@@ -0,0 +1,127 @@
+use clap::ErrorKind;
+
+use chip_8::cli::{self, Options, MAX_CYCLES_PER_FRAME};
+use chip_8::palette::{Rgb, Theme};
+use chip_8::phosphor::Persistence;
+use chip_8::policy::OpcodePolicy;
+use chip_8::quirks::QuirkPreset;
+use chip_8::speed::Speed;
+
+fn parse(args: &[&str]) -> Result<Options, clap::Error> {
+    cli::parse_from(std::iter::once("chip_8").chain(args.iter().copied()))
+}
+
+fn error(args: &[&str]) -> ErrorKind {
+    match parse(args) {
+        Ok(_) => panic!("{:?} parsed", args),
+        Err(e) => e.kind,
+    }
+}
+
+#[test]
+fn defaults() {
+    let options = parse(&[]).unwrap();
+    assert_eq!(options.rom, None);
+    assert_eq!(options.rom_dir, "./roms");
+    assert_eq!(options.scale, 10);
+    assert_eq!(options.cycles_per_frame, None);
+    assert!(!options.vip_timing);
+    assert!(options.platform.is_none());
+    assert_eq!(options.quirks, None);
+    assert_eq!(options.persistence, Persistence::Off);
+    assert_eq!(options.sys_policy, OpcodePolicy::Warn);
+    assert_eq!(options.unknown_policy, OpcodePolicy::Halt);
+    assert_eq!(options.turbo, Speed::Unlimited);
+}
+
+#[test]
+fn quirks_and_platforms_are_picked_by_name() {
+    let options = parse(&["roms/BRIX", "--quirks", "vip", "-p", "eti660"]).unwrap();
+    assert_eq!(options.rom.as_deref(), Some("roms/BRIX"));
+    assert_eq!(options.quirks, Some(QuirkPreset::Vip));
+    assert_eq!(options.platform.unwrap().name, "eti660");
+    assert_eq!(
+        parse(&["-q", "schip"]).unwrap().quirks,
+        Some(QuirkPreset::Schip)
+    );
+
+    assert_eq!(error(&["--quirks", "octo"]), ErrorKind::InvalidValue);
+    assert_eq!(error(&["--platform", "xochip"]), ErrorKind::InvalidValue);
+}
+
+#[test]
+fn colours_are_parsed_once_and_reported_like_validators() {
+    let options = parse(&["--fg", "#FF8000", "--bg", "102030"]).unwrap();
+    assert_eq!(options.foreground, Some(Rgb(0xFF, 0x80, 0x00)));
+    assert_eq!(options.background, Some(Rgb(0x10, 0x20, 0x30)));
+
+    let e = parse(&["--fg", "orange"]).err().unwrap();
+    assert_eq!(e.kind, ErrorKind::ValueValidation);
+    assert!(e.message.contains("Invalid value for '--fg'"), "{}", e);
+    assert_eq!(error(&["--bg", "#12345"]), ErrorKind::ValueValidation);
+}
+
+#[test]
+fn the_command_line_overrides_the_settings_file() {
+    let path = std::env::temp_dir().join(format!("chip_8-config-{}.json", std::process::id()));
+    std::fs::write(&path, r##"{ "theme": "lcd", "background": "#102030" }"##).unwrap();
+    let config = path.to_str().unwrap();
+
+    let options = parse(&["--config", config]).unwrap();
+    assert_eq!(options.theme, Some(Theme::Lcd));
+    assert_eq!(options.foreground, None);
+    assert_eq!(options.background, Some(Rgb(0x10, 0x20, 0x30)));
+    let options = parse(&["--config", config, "-t", "amber", "--bg", "#000000"]).unwrap();
+    assert_eq!(options.theme, Some(Theme::Amber));
+    assert_eq!(options.background, Some(Rgb(0, 0, 0)));
+
+    std::fs::write(&path, r#"{ "theme": "sepia" }"#).unwrap();
+    assert_eq!(error(&["--config", config]), ErrorKind::ValueValidation);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(error(&["--config", config]), ErrorKind::ValueValidation);
+}
+
+#[test]
+fn numbers_are_checked() {
+    let options = parse(&["-c", "15", "--scale", "4", "--fade", "6"]).unwrap();
+    assert_eq!(options.cycles_per_frame, Some(15));
+    assert_eq!(options.scale, 4);
+    assert_eq!(options.persistence, Persistence::Fade(6));
+    assert_eq!(
+        parse(&["--turbo", "1/4"]).unwrap().turbo,
+        Speed::Fraction(4)
+    );
+
+    assert_eq!(error(&["--scale", "0"]), ErrorKind::ValueValidation);
+    // as many cycles as the speed controls allow
+    assert_eq!(
+        parse(&["-c", "1000"]).unwrap().cycles_per_frame,
+        Some(MAX_CYCLES_PER_FRAME)
+    );
+    assert_eq!(error(&["-c", "1001"]), ErrorKind::ValueValidation);
+    assert_eq!(error(&["--turbo", "fast"]), ErrorKind::ValueValidation);
+}
+
+#[test]
+fn conflicting_options_are_rejected() {
+    assert_eq!(
+        error(&["--vip-timing", "-c", "10"]),
+        ErrorKind::ArgumentConflict
+    );
+    assert_eq!(
+        error(&["--fade", "3", "--blend", "2"]),
+        ErrorKind::ArgumentConflict
+    );
+    assert_eq!(
+        error(&["roms/BRIX", "--analyze", "--cfg", "dot"]),
+        ErrorKind::ArgumentConflict
+    );
+    assert_eq!(
+        error(&["roms/BRIX", "--movie", "demo.movie", "--analyze"]),
+        ErrorKind::ArgumentConflict
+    );
+
+    // analysing needs a ROM
+    assert_eq!(error(&["--analyze"]), ErrorKind::MissingRequiredArgument);
+    assert!(parse(&["roms/BRIX", "--analyze"]).unwrap().analyze);
+}
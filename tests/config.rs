@@ -0,0 +1,35 @@
+use chip_8::config::{Config, ConfigError};
+use chip_8::palette::{Rgb, Theme};
+
+#[test]
+fn settings_are_optional() {
+    let config = Config::parse(r##"{ "theme": "amber", "foreground": "#FFC040" }"##).unwrap();
+    assert_eq!(config.theme, Some(Theme::Amber));
+    assert_eq!(config.foreground, Some(Rgb(0xFF, 0xC0, 0x40)));
+    assert_eq!(config.background, None);
+    assert_eq!(Config::parse("{}").unwrap(), Config::default());
+}
+
+#[test]
+fn mistakes_are_reported() {
+    let value = |text: &str| match Config::parse(text) {
+        Err(ConfigError::Value(message)) => message,
+        other => panic!("expected an invalid value, got {:?}", other),
+    };
+    assert!(value(r#"{ "theme": "sepia" }"#).contains("unknown theme"));
+    assert!(value(r#"{ "background": "black" }"#).contains("invalid colour"));
+
+    // unknown settings are likely misspelt
+    assert!(matches!(
+        Config::parse(r##"{ "colour": "#000000" }"##),
+        Err(ConfigError::Syntax(_))
+    ));
+    assert!(matches!(
+        Config::parse("theme = amber"),
+        Err(ConfigError::Syntax(_))
+    ));
+    assert!(matches!(
+        Config::load("no/such/settings.json"),
+        Err(ConfigError::Io(_))
+    ));
+}
@@ -0,0 +1,123 @@
+use chip_8::database::Database;
+use chip_8::font::FontSet;
+use chip_8::quirks::{IndexIncrement, Quirks};
+use chip_8::rom::Rom;
+
+// a database of the given programs on the bundled platforms
+fn database(programs: &str) -> Database {
+    let platforms = std::fs::read_to_string("db/platforms.json").unwrap();
+    let hashes = r#"{"aa": 0, "bb": 0, "cc": 0, "dd": 0, "ee": 0, "ff": 0, "11": 0}"#;
+    Database::parse(programs, hashes, &platforms).unwrap()
+}
+
+fn quirks(database: &Database, sha1: &str) -> Quirks {
+    database.lookup(sha1).unwrap().quirks.unwrap()
+}
+
+#[test]
+fn bundled_roms_are_found_by_their_hash() {
+    let brix = Rom::load("roms/BRIX").unwrap();
+    let info = Database::bundled().lookup(&brix.sha1()).unwrap();
+    assert_eq!(info.title, "Brix");
+    assert_eq!(info.authors, vec!["Andreas Gustafsson"]);
+    assert_eq!(info.release.as_deref(), Some("1990"));
+    assert_eq!(info.platform.as_deref(), Some("originalChip8"));
+    assert_eq!(info.tickrate, Some(15));
+    assert_eq!(info.keys.get("left"), Some(&4));
+    assert_eq!(
+        info.quirks,
+        Some(Quirks {
+            shift_uses_vy: true,
+            load_store_index: IndexIncrement::ByXPlusOne,
+            logic_resets_vf: true,
+            jump_uses_vx: false,
+            clip_sprites: true,
+            display_wait: true,
+            stack_depth: Some(12),
+            stack_wraps: true,
+            font: FontSet::Vip,
+        })
+    );
+
+    // hashes are looked up in either case
+    let upper = brix.sha1().to_uppercase();
+    assert_eq!(Database::bundled().lookup(&upper).unwrap().title, "Brix");
+    assert!(Database::bundled().lookup("0000").is_none());
+}
+
+#[test]
+fn database_quirks_map_onto_ours() {
+    let database = database(
+        r#"[{"title": "Quirky", "roms": {
+            "aa": {"platforms": ["modernChip8"]},
+            "bb": {"platforms": ["modernChip8"], "tickrate": 7, "quirkyPlatforms":
+                {"modernChip8": {"shift": true, "wrap": true, "vblank": true, "jump": true}}},
+            "cc": {"platforms": ["superchip"]},
+            "dd": {"platforms": ["chip48"]},
+            "ee": {"platforms": ["unknown"]}
+        }}]"#,
+    );
+
+    let plain = quirks(&database, "aa");
+    assert!(plain.shift_uses_vy);
+    assert!(plain.clip_sprites);
+    assert!(!plain.display_wait);
+    assert!(!plain.jump_uses_vx);
+    assert_eq!(database.lookup("aa").unwrap().tickrate, Some(12));
+
+    // shift is the opposite of shift_uses_vy and wrap of clip_sprites
+    let quirky = quirks(&database, "bb");
+    assert!(!quirky.shift_uses_vy);
+    assert!(!quirky.clip_sprites);
+    assert!(quirky.display_wait);
+    assert!(quirky.jump_uses_vx);
+    assert_eq!(database.lookup("bb").unwrap().tickrate, Some(7));
+
+    assert_eq!(
+        quirks(&database, "cc").load_store_index,
+        IndexIncrement::Unchanged
+    );
+    assert_eq!(
+        quirks(&database, "dd").load_store_index,
+        IndexIncrement::ByX
+    );
+
+    // a platform the database doesn't describe has no quirks
+    let unknown = database.lookup("ee").unwrap();
+    assert_eq!(unknown.platform.as_deref(), Some("unknown"));
+    assert_eq!(unknown.quirks, None);
+    assert_eq!(unknown.tickrate, None);
+}
+
+#[test]
+fn stack_and_font_follow_the_platform() {
+    let database = database(
+        r#"[{"title": "Everywhere", "roms": {
+            "aa": {"platforms": ["originalChip8"]},
+            "bb": {"platforms": ["hybridVIP"]},
+            "cc": {"platforms": ["modernChip8"]},
+            "dd": {"platforms": ["chip48"]},
+            "ee": {"platforms": ["superchip1"]},
+            "ff": {"platforms": ["superchip"]},
+            "11": {"platforms": ["xochip"]}
+        }}]"#,
+    );
+    let expected = [
+        ("aa", Some(12), true, FontSet::Vip),
+        ("bb", Some(12), true, FontSet::Vip),
+        ("cc", None, false, FontSet::Chip48),
+        ("dd", Some(16), false, FontSet::Chip48),
+        ("ee", Some(16), false, FontSet::Schip),
+        ("ff", Some(16), false, FontSet::Schip),
+        ("11", None, false, FontSet::Schip),
+    ];
+    for &(sha1, stack_depth, stack_wraps, font) in &expected {
+        let quirks = quirks(&database, sha1);
+        assert_eq!(
+            (quirks.stack_depth, quirks.stack_wraps, quirks.font),
+            (stack_depth, stack_wraps, font),
+            "{}",
+            sha1
+        );
+    }
+}
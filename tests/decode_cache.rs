@@ -0,0 +1,40 @@
+use chip_8::chip8::Chip8;
+use chip_8::rom::Rom;
+
+#[rustfmt::skip]
+const SELF_MODIFYING: [u8; 26] = [
+    0x72, 0x01, // 0x200: ADD V2, 1, becomes ADD V2, 5 and then JP 0x216
+    0xA2, 0x01, // 0x202: LD I, 0x201
+    0x60, 0x05, // 0x204: LD V0, 5
+    0xF0, 0x55, // 0x206: LD [I], V0 rewriting the second byte of 0x200
+    0x32, 0x06, // 0x208: SE V2, 6 once 0x200 added 5
+    0x12, 0x00, // 0x20A: JP 0x200
+    0xA2, 0x00, // 0x20C: LD I, 0x200
+    0x60, 0x12, // 0x20E: LD V0, 0x12
+    0x61, 0x16, // 0x210: LD V1, 0x16
+    0xF1, 0x55, // 0x212: LD [I], V1 rewriting both bytes of 0x200
+    0x12, 0x00, // 0x214: JP 0x200
+    0x63, 0x42, // 0x216: LD V3, 0x42
+    0x12, 0x18, // 0x218: JP 0x218
+];
+
+fn run(decode_cache: bool) -> Chip8 {
+    let mut chip8 = Chip8::new();
+    chip8.set_decode_cache(decode_cache);
+    chip8.init();
+    chip8
+        .load_rom(Rom::from_binary(SELF_MODIFYING.to_vec()))
+        .unwrap();
+    chip8.run_cycles(40);
+    chip8
+}
+
+#[test]
+fn rewritten_instructions_are_decoded_again() {
+    let cached = run(true);
+    let uncached = run(false);
+    assert_eq!(cached.snapshot(), uncached.snapshot());
+    let regs = cached.snapshot().regs;
+    assert_eq!((regs[2], regs[3]), (6, 0x42));
+    assert_eq!(cached.snapshot().pc, 0x218);
+}
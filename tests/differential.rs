@@ -0,0 +1,212 @@
+mod reference;
+
+use std::fs;
+
+use chip_8::chip8::{Chip8, Snapshot};
+use chip_8::quirks::{QuirkPreset, Quirks};
+use chip_8::rom::Rom;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use reference::Reference;
+
+const SEED: u64 = 0x5EED;
+const CYCLES_PER_FRAME: u32 = 10;
+const ROM_STEPS: u32 = 3_000;
+const PROGRAMS: u64 = 100;
+const PROGRAM_LEN: u16 = 96; // instructions
+const PROGRAM_STEPS: u32 = 1_000;
+const PRESETS: [QuirkPreset; 3] = [QuirkPreset::Chip8, QuirkPreset::Vip, QuirkPreset::Schip];
+
+// key presses and releases as (step, key, pressed)
+type Inputs = Vec<(u32, u8, bool)>;
+
+fn random_inputs(rng: &mut StdRng, steps: u32) -> Inputs {
+    let mut inputs = Vec::new();
+    let mut step = 0;
+    while step < steps {
+        let key = rng.gen_range(0, 16);
+        let held = rng.gen_range(50, 500);
+        inputs.push((step, key, true));
+        inputs.push((step + held, key, false));
+        step += rng.gen_range(100, 1000);
+    }
+    inputs.sort_by_key(|(step, _, _)| *step);
+    inputs
+}
+
+// Runs both machines in lock step until the reference stops or the step
+// limit is reached. Returns a description of the first divergence.
+fn run(rom: &[u8], quirks: Quirks, inputs: &[(u32, u8, bool)], steps: u32) -> Result<(), String> {
+    let mut core = Chip8::new();
+    core.set_quirks(quirks);
+    // built with the dynarec, it is what gets compared with the reference
+    #[cfg(feature = "dynarec")]
+    core.set_dynarec(true);
+    core.seed(SEED);
+    core.init();
+    core.load_rom(Rom::from_binary(rom.to_vec())).unwrap();
+    let mut reference = Reference::new(rom, quirks, SEED);
+
+    let mut inputs = inputs.iter().peekable();
+    for step in 0..steps {
+        while let Some((_, key, pressed)) = inputs.next_if(|(at, _, _)| *at == step) {
+            reference.set_key(*key, *pressed);
+            if *pressed {
+                core.set_key(*key);
+            } else {
+                core.unset_key(*key);
+            }
+        }
+        if step > 0 && step % CYCLES_PER_FRAME == 0 {
+            reference.tick_timers();
+            core.tick_timers();
+        }
+
+        let pc = reference.pc();
+        let opcode = match reference.next_opcode() {
+            Some(opcode) => opcode,
+            None => return Ok(()),
+        };
+        if reference.step().is_err() {
+            // nothing left to compare against
+            return Ok(());
+        }
+        let describe = |problem: String| {
+            format!(
+                "step {}: 0x{:04X} at 0x{:03X}: {}",
+                step, opcode, pc, problem
+            )
+        };
+        core.run_cycles(1);
+        if let Some(fault) = core.fault() {
+            return Err(describe(format!("the core halted with {}", fault)));
+        }
+        let (expected, actual) = (reference.snapshot(), core.snapshot());
+        if expected != actual {
+            return Err(describe(compare(&expected, &actual).join(", ")));
+        }
+    }
+    Ok(())
+}
+
+fn compare(expected: &Snapshot, actual: &Snapshot) -> Vec<String> {
+    let mut differences = Vec::new();
+    let mut field = |name: &str, expected: String, actual: String| {
+        if expected != actual {
+            differences.push(format!("{} is {} instead of {}", name, actual, expected));
+        }
+    };
+    for r in 0..expected.regs.len() {
+        field(
+            &format!("V{:X}", r),
+            format!("0x{:02X}", expected.regs[r]),
+            format!("0x{:02X}", actual.regs[r]),
+        );
+    }
+    field(
+        "I",
+        format!("0x{:03X}", expected.i_reg),
+        format!("0x{:03X}", actual.i_reg),
+    );
+    field(
+        "PC",
+        format!("0x{:03X}", expected.pc),
+        format!("0x{:03X}", actual.pc),
+    );
+    field(
+        "stack",
+        format!("{:03X?}", expected.stack),
+        format!("{:03X?}", actual.stack),
+    );
+    field(
+        "DT",
+        expected.delay_timer.to_string(),
+        actual.delay_timer.to_string(),
+    );
+    field(
+        "ST",
+        expected.sound_timer.to_string(),
+        actual.sound_timer.to_string(),
+    );
+    let pixels = expected
+        .screen
+        .iter()
+        .zip(&actual.screen)
+        .filter(|(expected, actual)| expected != actual)
+        .count();
+    if pixels > 0 {
+        differences.push(format!("{} pixels differ", pixels));
+    }
+    differences
+}
+
+// a random but well-formed program: only supported opcodes, with jumps and
+// calls landing on instructions of the program
+fn random_program(rng: &mut StdRng) -> Vec<u8> {
+    let mut program = Vec::new();
+    for _ in 0..PROGRAM_LEN {
+        let target = 0x200 + 2 * rng.gen_range(0, PROGRAM_LEN);
+        let x = rng.gen_range(0, 16);
+        let y = rng.gen_range(0, 16);
+        let nn = rng.gen::<u8>() as u16;
+        let opcode = match rng.gen_range(0, 30) {
+            0 => 0x00E0,
+            1 => 0x00EE,
+            2 => 0x1000 | target,
+            3 => 0x2000 | target,
+            4 => 0x3000 | x << 8 | nn,
+            5 => 0x4000 | x << 8 | nn,
+            6 => 0x5000 | x << 8 | y << 4,
+            7..=9 => 0x6000 | x << 8 | nn,
+            10..=11 => 0x7000 | x << 8 | nn,
+            12 => 0x8000 | x << 8 | y << 4 | [0x0, 0x1, 0x2, 0x3][rng.gen_range(0, 4)],
+            13 => 0x8000 | x << 8 | y << 4 | [0x4, 0x5, 0x6, 0x7, 0xE][rng.gen_range(0, 5)],
+            14 => 0x9000 | x << 8 | y << 4,
+            15..=16 => 0xA000 | rng.gen_range(0x200, 0xF00),
+            17 => 0xB000 | (target - 0x100),
+            18 => 0xC000 | x << 8 | nn,
+            19..=20 => 0xD000 | x << 8 | y << 4 | rng.gen_range(0, 16),
+            21 => 0xE09E | x << 8,
+            22 => 0xE0A1 | x << 8,
+            23 => 0xF000 | x << 8 | [0x07, 0x0A, 0x15, 0x18][rng.gen_range(0, 4)],
+            24 => 0xF01E | x << 8,
+            25 => 0xF029 | x << 8,
+            26 => 0xF033 | x << 8,
+            27 => 0xF055 | x << 8,
+            _ => 0xF065 | x << 8,
+        };
+        program.push((opcode >> 8) as u8);
+        program.push(opcode as u8);
+    }
+    program
+}
+
+#[test]
+fn roms_match_reference() {
+    let mut rng = StdRng::seed_from_u64(SEED);
+    for entry in fs::read_dir("roms").unwrap() {
+        let path = entry.unwrap().path();
+        let rom: Vec<u8> = Rom::load(&path).unwrap().into();
+        let inputs = random_inputs(&mut rng, ROM_STEPS);
+        for preset in &PRESETS {
+            if let Err(divergence) = run(&rom, Quirks::from_preset(*preset), &inputs, ROM_STEPS) {
+                panic!("{:?} with {} quirks, {}", path, preset, divergence);
+            }
+        }
+    }
+}
+
+#[test]
+fn random_programs_match_reference() {
+    for seed in 0..PROGRAMS {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let program = random_program(&mut rng);
+        let inputs = random_inputs(&mut rng, PROGRAM_STEPS);
+        for preset in &PRESETS {
+            let quirks = Quirks::from_preset(*preset);
+            if let Err(divergence) = run(&program, quirks, &inputs, PROGRAM_STEPS) {
+                panic!("program {} with {} quirks, {}", seed, preset, divergence);
+            }
+        }
+    }
+}
@@ -0,0 +1,104 @@
+#![cfg(feature = "dynarec")]
+
+use std::fs;
+
+use chip_8::chip8::Chip8;
+use chip_8::rom::Rom;
+
+const BATCHES: u32 = 2000;
+const BATCH_SIZE: u32 = 7; // odd so batches end in the middle of blocks
+
+// The first pass compiles 0x200 to 0x20E into one block, jump included, whose
+// store then rewrites 0x202 in the middle of it.
+#[rustfmt::skip]
+const SELF_MODIFYING: [u8; 20] = [
+    0x74, 0x01, // 0x200: ADD V4, 1
+    0x75, 0x01, // 0x202: ADD V5, 1, becomes ADD V5, 0x10
+    0x34, 0x02, // 0x204: SE V4, 2 on the second pass
+    0x12, 0x0A, // 0x206: JP 0x20A
+    0x12, 0x08, // 0x208: JP 0x208
+    0xA2, 0x03, // 0x20A: LD I, 0x203
+    0x60, 0x10, // 0x20C: LD V0, 0x10
+    0xF0, 0x55, // 0x20E: LD [I], V0
+    0x12, 0x00, // 0x210: JP 0x200
+    0x00, 0x00,
+];
+
+fn machine(path: &std::path::Path, dynarec: bool) -> Chip8 {
+    load(Rom::load(path).unwrap(), dynarec)
+}
+
+fn load(rom: Rom, dynarec: bool) -> Chip8 {
+    let mut chip8 = Chip8::new();
+    chip8.seed(0);
+    chip8.set_dynarec(dynarec);
+    chip8.init();
+    chip8.load_rom(rom).unwrap();
+    chip8
+}
+
+#[test]
+fn dynarec_matches_interpreter_on_all_roms() {
+    for entry in fs::read_dir("roms").unwrap() {
+        let path = entry.unwrap().path();
+        let mut interpreter = machine(&path, false);
+        let mut dynarec = machine(&path, true);
+        for batch in 0..BATCHES {
+            // hold down a different key every now and then
+            if batch % 50 == 0 {
+                let key = (batch / 50 % 16) as u8;
+                interpreter.set_key(key);
+                dynarec.set_key(key);
+            } else if batch % 50 == 25 {
+                let key = (batch / 50 % 16) as u8;
+                interpreter.unset_key(key);
+                dynarec.unset_key(key);
+            }
+            interpreter.run_cycles(BATCH_SIZE);
+            dynarec.run_cycles(BATCH_SIZE);
+            // both engines have to halt on the same fault
+            assert_eq!(
+                interpreter.fault(),
+                dynarec.fault(),
+                "{:?}: faults differ in batch {}",
+                path,
+                batch
+            );
+            interpreter.tick_timers();
+            dynarec.tick_timers();
+            assert_eq!(
+                interpreter.redraw(),
+                dynarec.redraw(),
+                "{:?}: redraw differs in batch {}",
+                path,
+                batch
+            );
+            assert_eq!(
+                interpreter.snapshot(),
+                dynarec.snapshot(),
+                "{:?}: state differs in batch {}",
+                path,
+                batch
+            );
+        }
+    }
+}
+
+#[test]
+fn stores_into_compiled_code_are_run_as_written() {
+    // every batch size leaves different blocks cached, starting at and
+    // containing the rewritten instruction
+    for batch in 1..=8 {
+        let rom = || Rom::from_binary(SELF_MODIFYING.to_vec());
+        let mut interpreter = load(rom(), false);
+        let mut dynarec = load(rom(), true);
+        for _ in 0..40 / batch {
+            interpreter.run_cycles(batch);
+            dynarec.run_cycles(batch);
+            assert_eq!(interpreter.snapshot(), dynarec.snapshot(), "{}", batch);
+        }
+        let snapshot = dynarec.snapshot();
+        assert_eq!((snapshot.regs[4], snapshot.regs[5]), (2, 0x11), "{}", batch);
+        assert_eq!(snapshot.pc, 0x208, "{}", batch);
+    }
+}
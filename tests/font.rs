@@ -0,0 +1,66 @@
+use chip_8::chip8::Chip8;
+use chip_8::font::{Font, FontError, FontSet};
+use chip_8::quirks::{QuirkPreset, Quirks};
+use chip_8::rom::Rom;
+
+// LD F, V0 with V0 = 1, then draw the glyph at V1, V1 = 0, 0
+const DRAW_ONE: [u8; 8] = [0x60, 0x01, 0xF0, 0x29, 0xD1, 0x15, 0x12, 0x06];
+
+fn first_glyph_row(chip8: &mut Chip8) -> Vec<bool> {
+    chip8.load_rom(Rom::from_binary(DRAW_ONE.to_vec())).unwrap();
+    chip8.run_cycles(3);
+    chip8.snapshot().screen[..8].to_vec()
+}
+
+#[test]
+fn quirk_presets_select_their_font() {
+    for (preset, row) in [(QuirkPreset::Chip8, 0x20), (QuirkPreset::Vip, 0x60)] {
+        let mut chip8 = Chip8::new();
+        chip8.set_quirks(Quirks::from_preset(preset));
+        chip8.init();
+        let expected: Vec<bool> = (0..8).map(|x| row & (0x80 >> x) != 0).collect();
+        assert_eq!(first_glyph_row(&mut chip8), expected, "{}", preset);
+    }
+}
+
+#[test]
+fn custom_font_replaces_the_quirks_font() {
+    let mut data = vec![0; 80];
+    data[5] = 0xFF; // top row of the 1
+    let mut chip8 = Chip8::new();
+    chip8.set_quirks(Quirks::from_preset(QuirkPreset::Vip));
+    chip8.set_font(Font::from_bytes(&data).unwrap());
+    chip8.init();
+    assert_eq!(first_glyph_row(&mut chip8), vec![true; 8]);
+}
+
+#[test]
+fn font_files_need_whole_glyph_sets() {
+    assert!(Font::from_bytes(&[0; 80]).unwrap().big().is_none());
+    assert_eq!(
+        Font::from_bytes(&[0; 180]).unwrap().big().unwrap().len(),
+        100
+    );
+    assert_eq!(
+        Font::from_bytes(&[0; 240]).unwrap().big().unwrap().len(),
+        160
+    );
+    for size in [0, 79, 81, 190] {
+        assert!(matches!(
+            Font::from_bytes(&vec![0; size]),
+            Err(FontError::Size(s)) if s == size
+        ));
+    }
+}
+
+#[test]
+fn builtin_fonts_are_complete() {
+    for name in FontSet::NAMES {
+        let font = Font::builtin(name.parse().unwrap());
+        assert_eq!(font.small().len(), 80, "{}", name);
+    }
+    assert_eq!(
+        Font::builtin(FontSet::Schip).big().map(<[u8]>::len),
+        Some(100)
+    );
+}
@@ -0,0 +1,112 @@
+use chip_8::chip8::Chip8;
+use chip_8::font::Font;
+use chip_8::memory_map::{Access, MemoryMap, RegionKind, Violation};
+use chip_8::rom::Rom;
+
+fn machine(rom: &[u8]) -> Chip8 {
+    let mut chip8 = Chip8::new();
+    chip8.set_check_memory(true);
+    chip8.init();
+    chip8.load_rom(Rom::from_binary(rom.to_vec())).unwrap();
+    chip8
+}
+
+#[test]
+fn regions_cover_the_interpreter_layouts() {
+    let map = MemoryMap::chip8();
+    let kind = |addr| map.region_at(addr).map(|region| region.kind);
+    assert_eq!(kind(0x000), Some(RegionKind::Interpreter));
+    assert_eq!(kind(0x050), Some(RegionKind::Font));
+    assert_eq!(kind(0x0A0), Some(RegionKind::BigFont));
+    assert_eq!(kind(0x104), Some(RegionKind::Interpreter));
+    assert_eq!(kind(0xFFF), Some(RegionKind::Program));
+    assert_eq!(kind(0x1000), None);
+
+    let map = MemoryMap::vip();
+    let kind = |addr| map.region_at(addr).map(|region| region.kind);
+    assert_eq!(kind(0x0A0), Some(RegionKind::Interpreter));
+    assert_eq!(kind(0xE9F), Some(RegionKind::Program));
+    assert_eq!(kind(0xEA0), Some(RegionKind::Reserved));
+}
+
+#[test]
+fn writes_into_the_font_are_flagged() {
+    // LD I, 0x050; LD B, V0
+    let mut chip8 = machine(&[0xA0, 0x50, 0xF0, 0x33]);
+    chip8.run_cycles(2);
+    let violations = chip8.take_violations();
+    assert_eq!(violations.len(), 3);
+    assert_eq!(
+        violations[0],
+        Violation {
+            addr: 0x202,
+            target: 0x050,
+            access: Access::Write,
+            region: Some(RegionKind::Font),
+        }
+    );
+    assert!(chip8.take_violations().is_empty());
+}
+
+#[test]
+fn the_big_font_region_fits_the_font() {
+    // LD I, 0x118; LD B, V0 writing into the 13th of 16 big glyphs
+    let rom = [0xA1, 0x18, 0xF0, 0x33];
+    let mut chip8 = Chip8::new();
+    chip8.set_font(Font::from_bytes(&[0xFF; 16 * 5 + 16 * 10]).unwrap());
+    chip8.set_check_memory(true);
+    chip8.init();
+    chip8.load_rom(Rom::from_binary(rom.to_vec())).unwrap();
+    chip8.run_cycles(2);
+    let regions: Vec<_> = chip8
+        .take_violations()
+        .iter()
+        .map(|violation| violation.region)
+        .collect();
+    assert_eq!(regions, vec![Some(RegionKind::BigFont); 3]);
+
+    let big_font = chip8.memory_map().region(RegionKind::BigFont).unwrap();
+    assert_eq!((big_font.start, big_font.end), (0x0A0, 0x140));
+    assert_eq!(Chip8::new().memory_map().region(RegionKind::BigFont), None);
+}
+
+#[test]
+fn reads_past_the_end_of_memory_are_flagged() {
+    // LD I, 0xFFE; DRW V0, V0, 4
+    let mut chip8 = machine(&[0xAF, 0xFE, 0xD0, 0x04]);
+    chip8.run_cycles(2);
+    let targets: Vec<_> = chip8
+        .take_violations()
+        .iter()
+        .map(|violation| (violation.target, violation.region))
+        .collect();
+    assert_eq!(targets, vec![(0x1000, None), (0x1001, None)]);
+}
+
+#[test]
+fn program_memory_is_not_flagged() {
+    // LD I, 0x300; LD [I], V3; LD V3, [I]
+    let mut chip8 = machine(&[0xA3, 0x00, 0xF3, 0x55, 0xF3, 0x65]);
+    chip8.run_cycles(3);
+    assert!(chip8.take_violations().is_empty());
+}
+
+#[cfg(feature = "dynarec")]
+#[test]
+fn dynarec_reports_the_instruction_making_the_access() {
+    // LD I, 0xFFF; LD V0, 0; LD V1, [I]; LD V2, 0; DRW V0, V0, 2 as one block,
+    // reading past the end of memory from 0x204 and 0x208
+    let rom = [0xAF, 0xFF, 0x60, 0x00, 0xF1, 0x65, 0x62, 0x00, 0xD0, 0x02];
+    let violations = |dynarec| {
+        let mut chip8 = machine(&rom);
+        chip8.set_dynarec(dynarec);
+        chip8.run_cycles(5);
+        chip8
+            .take_violations()
+            .iter()
+            .map(|violation| violation.addr)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(violations(true), vec![0x204, 0x208]);
+    assert_eq!(violations(true), violations(false));
+}
@@ -0,0 +1,66 @@
+use chip_8::chip8::Chip8;
+use chip_8::movie::{Movie, MovieError};
+use chip_8::rom::Rom;
+
+#[test]
+fn keys_change_on_their_frames() {
+    let movie = Movie::parse("# a comment\n\n10 5\n20 58 # two keys\n30 -\n40 end\n").unwrap();
+    assert_eq!(movie.len(), 40);
+    assert_eq!(movie.keys(0), 0);
+    assert_eq!(movie.keys(10), 1 << 5);
+    assert_eq!(movie.keys(25), 1 << 5 | 1 << 8);
+    assert_eq!(movie.keys(30), 0);
+}
+
+#[test]
+fn without_an_end_the_last_change_ends_the_movie() {
+    let movie = Movie::parse("3 a\n").unwrap();
+    assert_eq!(movie.len(), 4);
+    assert_eq!(movie.keys(3), 1 << 0xA);
+    assert!(Movie::parse("").unwrap().is_empty());
+}
+
+fn syntax_error_line(text: &str) -> usize {
+    match Movie::parse(text) {
+        Err(MovieError::Syntax { line, .. }) => line,
+        other => panic!("expected a syntax error, got {:?}", other),
+    }
+}
+
+#[test]
+fn errors_name_the_line() {
+    assert_eq!(syntax_error_line("x 5\n"), 1);
+    assert_eq!(syntax_error_line("1 5\n2 g\n"), 2);
+    assert_eq!(syntax_error_line("5 1\n5 2\n"), 2);
+    assert_eq!(syntax_error_line("5 1\n3 end\n"), 2);
+    assert_eq!(syntax_error_line("5 end\n6 1\n"), 2);
+    assert_eq!(syntax_error_line("5\n"), 1);
+    // the movie would have to end after the last frame number
+    assert_eq!(syntax_error_line("1 5\n4294967295 1\n"), 2);
+    assert_eq!(syntax_error_line("4294967295 -\n"), 1);
+    assert_eq!(Movie::parse("4294967295 end\n").unwrap().len(), u32::MAX);
+}
+
+#[test]
+fn play_holds_the_keys_for_each_frame() {
+    // counts the frames key 0 is held in V0, three instructions either way
+    let rom = vec![
+        0xE1, 0x9E, // 200: SKP V1
+        0x12, 0x06, // 202: JP 206
+        0x70, 0x01, // 204: ADD V0, 1
+        0x12, 0x00, // 206: JP 200
+    ];
+    let mut chip8 = Chip8::new();
+    chip8.init();
+    chip8.load_rom(Rom::from_binary(rom)).unwrap();
+
+    let movie = Movie::parse("2 0\n4 -\n6 end\n").unwrap();
+    let mut held = Vec::new();
+    let result: Result<(), ()> = movie.play(&mut chip8, |chip8| {
+        held.push(chip8.snapshot().regs[0]);
+        Ok(())
+    });
+    assert!(result.is_ok());
+    assert_eq!(held, vec![0, 0, 1, 2, 2, 2]);
+    assert_eq!(chip8.frame(), 6);
+}
@@ -0,0 +1,366 @@
+use chip_8::chip8::Chip8;
+use chip_8::octo;
+use chip_8::rom::{Rom, RomError, RomFormat};
+
+// a GIF cartridge whose pixels hold the size and then the payload
+fn cartridge(size: u32, payload: &[u8]) -> Vec<u8> {
+    let mut bytes = size.to_be_bytes().to_vec();
+    bytes.extend_from_slice(payload);
+    let mut indices: Vec<u8> = bytes
+        .iter()
+        .flat_map(|b| vec![b >> 6, b >> 4 & 3, b >> 2 & 3, b & 3])
+        .collect();
+    let width = 64;
+    // whole rows, the padding after the payload is never read
+    let height = indices.len() / width + 1;
+    indices.resize(height * width, 0);
+    let height = height as u16;
+
+    let mut image = Vec::new();
+    {
+        let palette = [0, 0, 0, 85, 85, 85, 170, 170, 170, 255, 255, 255];
+        let mut encoder = gif::Encoder::new(&mut image, width as u16, height, &palette).unwrap();
+        let frame = gif::Frame::from_indexed_pixels(width as u16, height, &indices, None);
+        encoder.write_frame(&frame).unwrap();
+    }
+    image
+}
+
+fn run(source: &str) -> [u8; 16] {
+    let mut chip8 = Chip8::new();
+    chip8.init();
+    chip8
+        .load_rom(Rom::from_binary(octo::assemble(source).unwrap()))
+        .unwrap();
+    chip8.run_cycles(40);
+    chip8.snapshot().regs
+}
+
+fn error(source: &str) -> (usize, String) {
+    let error = octo::assemble(source).unwrap_err();
+    (error.line, error.message)
+}
+
+#[test]
+fn assembles_loops_constants_and_data() {
+    let rom = octo::assemble(
+        ":const COUNT 3
+        :alias counter v4
+        : main
+            counter := COUNT
+            i := data
+            loop
+                counter -= 1
+            while counter != 0 again
+        : halt
+            jump halt
+        : data
+            0xFF 0b1010 # two bytes",
+    )
+    .unwrap();
+    #[rustfmt::skip]
+    assert_eq!(
+        rom,
+        vec![
+            0x12, 0x02, // 0x200: JP main
+            0x64, 0x03, // 0x202: LD V4, 3
+            0xA2, 0x10, // 0x204: LD I, data
+            0x74, 0xFF, // 0x206: ADD V4, -1
+            0x44, 0x00, // 0x208: SNE V4, 0
+            0x12, 0x0E, // 0x20A: JP halt
+            0x12, 0x06, // 0x20C: JP 0x206
+            0x12, 0x0E, // 0x20E: JP halt
+            0xFF, 0x0A, // 0x210: data
+        ]
+    );
+}
+
+#[test]
+fn assembles_branches_and_addresses() {
+    let rom = octo::assemble(
+        ": main
+            if v1 == v2 then v3 := 1
+            if v1 key begin
+                sprite v1 v2 5
+            else
+                save v1 - v3
+            end
+            :unpack 0xA data
+            i := long data
+        : data
+            :next second 0x00 0x42
+            i := second",
+    )
+    .unwrap();
+    #[rustfmt::skip]
+    assert_eq!(
+        rom,
+        vec![
+            0x12, 0x02, // 0x200: JP main
+            0x91, 0x20, // 0x202: SNE V1, V2
+            0x63, 0x01, // 0x204: LD V3, 1
+            0xE1, 0x9E, // 0x206: SKP V1
+            0x12, 0x0E, // 0x208: JP else
+            0xD1, 0x25, // 0x20A: DRW V1, V2, 5
+            0x12, 0x10, // 0x20C: JP end
+            0x51, 0x32, // 0x20E: save v1 - v3
+            0x60, 0xA2, // 0x210: LD V0, 0xA0 | data >> 8
+            0x61, 0x18, // 0x212: LD V1, data & 0xFF
+            0xF0, 0x00, // 0x214: i := long
+            0x02, 0x18, // 0x216: data
+            0x00, 0x42, // 0x218: data
+            0xA2, 0x19, // 0x21A: LD I, second
+        ]
+    );
+}
+
+#[test]
+fn comparisons_subtract_in_vf() {
+    let rom = octo::assemble(
+        ":alias compare-temp ve
+        : main
+            if v1 < 3 then v0 := 1
+            if v1 > v2 then v0 := 2",
+    )
+    .unwrap();
+    #[rustfmt::skip]
+    assert_eq!(
+        rom[2..],
+        [
+            0x6E, 0x03, // LD VE, 3
+            0x8E, 0x17, // SUBN VE, V1
+            0x3F, 0x01, // SE VF, 1
+            0x60, 0x01, // LD V0, 1
+            0x8E, 0x20, // LD VE, V2
+            0x8E, 0x15, // SUB VE, V1
+            0x3F, 0x01, // SE VF, 1
+            0x60, 0x02, // LD V0, 2
+        ]
+    );
+}
+
+#[test]
+fn comparisons_hold_when_they_should() {
+    let pairs = [(0, 0), (1, 2), (2, 1), (7, 7), (0, 255), (255, 0)];
+    for &op in &["<", ">", "<=", ">=", "==", "!="] {
+        for &(a, b) in &pairs {
+            let regs = run(&format!(
+                ": main
+                    v1 := {a}
+                    v2 := {b}
+                    if v1 {op} v2 then v3 := 1
+                    if v1 {op} {b} begin v4 := 1 else v4 := 2 end
+                    v5 := 0
+                    loop
+                        v5 += 1
+                    while v1 {op} v2
+                    while v5 != 3
+                    again
+                : halt
+                    jump halt",
+                a = a,
+                b = b,
+                op = op
+            ));
+            let holds = match op {
+                "<" => a < b,
+                ">" => a > b,
+                "<=" => a <= b,
+                ">=" => a >= b,
+                "==" => a == b,
+                _ => a != b,
+            };
+            let expected = match holds {
+                true => (1, 1, 3),
+                false => (0, 2, 1),
+            };
+            assert_eq!((regs[3], regs[4], regs[5]), expected, "{} {} {}", a, op, b);
+        }
+    }
+}
+
+#[test]
+fn reports_broken_source() {
+    let cases = [
+        (": main\njump nowhere", 2, "undefined name `nowhere`"),
+        (": main\n: main", 2, "label `main` is defined twice"),
+        (": main\nloop", 2, "`loop` without matching `again`"),
+        (": main\nend", 2, "`end` without `begin`"),
+        (": main\nv0 := 256", 2, "256 does not fit into a byte"),
+        (": main\nif v0 ~ v1 then", 2, "unsupported condition `~`"),
+        (": main\n:macro twice", 2, "`:macro` is not supported"),
+        (": main\n:org 0x100", 2, ":org address 0x100 out of range"),
+        (
+            ": main\n:org 0xFFFF\n:next x",
+            3,
+            "program does not fit into memory",
+        ),
+        (
+            ": main\n:org 0xFFFF\n:unpack 1 main",
+            3,
+            "program does not fit into memory",
+        ),
+        (": main\nv0 :=", 2, "unexpected end of program"),
+        (": main\nv0 := 0x+5", 2, "invalid number `0x+5`"),
+        (": main\nv0 := 0b-1", 2, "invalid number `0b-1`"),
+    ];
+    for &(source, line, message) in &cases {
+        assert_eq!(error(source), (line, message.to_string()), "{:?}", source);
+    }
+}
+
+#[test]
+fn signs_after_the_radix_are_rejected() {
+    // from_str_radix took the inner sign and negating i32::MIN overflowed
+    let source = ": main v0 := -0x-80000000";
+    assert_eq!(
+        error(source),
+        (1, "invalid number `-0x-80000000`".to_string())
+    );
+    assert!(matches!(
+        Rom::from_bytes(source.as_bytes().to_vec()),
+        Err(RomError::Assemble(_))
+    ));
+}
+
+#[test]
+fn cartridges_carry_the_program_and_its_options() {
+    let payload = br##"{"program": ": main jump main", "options": {
+        "tickrate": 20, "shiftQuirks": true, "clipQuirks": true,
+        "backgroundColor": "#000000", "fillColor": "#FF0000"}}"##;
+    let image = cartridge(payload.len() as u32, payload);
+    assert_eq!(RomFormat::detect(&image), RomFormat::OctoCartridge);
+
+    let cartridge = octo::read_cartridge(&image).unwrap();
+    assert_eq!(cartridge.program, ": main jump main");
+
+    let rom = Rom::from_bytes(image).unwrap();
+    assert_eq!(rom.data(), &[0x12, 0x02, 0x12, 0x02]);
+    let options = rom.options().unwrap();
+    assert_eq!(options.tickrate, Some(20));
+    assert_eq!(options.colors(), vec!["#000000", "#FF0000"]);
+    let quirks = options.quirks();
+    assert!(!quirks.shift_uses_vy);
+    assert!(quirks.clip_sprites);
+    assert!(!quirks.display_wait);
+}
+
+#[test]
+fn cartridge_labels_and_frames_are_skipped() {
+    // the label art lives in the upper bits of each index and the payload
+    // may continue on a later frame
+    let payload = br#"{"program": ": main jump main"}"#;
+    let mut bytes = (payload.len() as u32).to_be_bytes().to_vec();
+    bytes.extend_from_slice(payload);
+    let indices: Vec<u8> = bytes
+        .iter()
+        .flat_map(|b| vec![b >> 6, b >> 4 & 3, b >> 2 & 3, b & 3])
+        .enumerate()
+        .map(|(i, bits)| (i as u8 % 61) << 2 | bits)
+        .collect();
+    let (first, second) = indices.split_at(64);
+    let palette: Vec<u8> = (0..=255).flat_map(|i| vec![i, i, i]).collect();
+
+    let mut image = Vec::new();
+    {
+        let mut encoder = gif::Encoder::new(&mut image, 64, 4, &palette).unwrap();
+        encoder
+            .write_frame(&gif::Frame::from_indexed_pixels(64, 1, first, None))
+            .unwrap();
+        let mut rest = second.to_vec();
+        rest.resize(64 * 4, 0);
+        encoder
+            .write_frame(&gif::Frame::from_indexed_pixels(64, 4, &rest, None))
+            .unwrap();
+    }
+    assert_eq!(
+        octo::read_cartridge(&image).unwrap().program,
+        ": main jump main"
+    );
+}
+
+#[test]
+fn reports_broken_cartridges() {
+    let message = |image: &[u8]| octo::read_cartridge(image).err().unwrap();
+
+    let mut garbage = b"GIF89a".to_vec();
+    garbage.extend_from_slice(&[0xFF; 20]);
+    assert!(message(&garbage).starts_with("invalid GIF image"));
+    assert!(matches!(
+        Rom::from_bytes(garbage),
+        Err(RomError::Cartridge(_))
+    ));
+
+    let payload = br#"{"program": ": main jump main"}"#;
+    let image = cartridge(1000, payload);
+    assert!(message(&image).starts_with("cartridge payload is truncated"));
+
+    let image = cartridge(5, b"{\"pro");
+    assert!(message(&image).starts_with("invalid cartridge payload"));
+
+    let image = cartridge(2, b"{}");
+    assert!(message(&image).starts_with("invalid cartridge payload"));
+
+    // a cartridge whose program doesn't assemble
+    let payload = br#"{"program": ": main jump nowhere"}"#;
+    let image = cartridge(payload.len() as u32, payload);
+    assert!(matches!(Rom::from_bytes(image), Err(RomError::Assemble(_))));
+}
+
+#[test]
+fn formats_are_detected_by_content() {
+    assert_eq!(RomFormat::detect(b"GIF87a"), RomFormat::OctoCartridge);
+    assert_eq!(
+        RomFormat::detect(b": main\n\tjump main\r\n"),
+        RomFormat::OctoSource
+    );
+    assert_eq!(
+        RomFormat::detect(&[0x00, 0xE0, 0x12, 0x00]),
+        RomFormat::Binary
+    );
+    assert_eq!(RomFormat::detect(&[0xC3, 0x28]), RomFormat::Binary);
+    assert_eq!(RomFormat::detect(&[]), RomFormat::Binary);
+
+    let rom = Rom::from_bytes(b": main jump main".to_vec()).unwrap();
+    assert_eq!(rom.data(), &[0x12, 0x02, 0x12, 0x02]);
+    assert!(rom.options().is_none());
+}
+
+#[test]
+fn printable_binaries_load_as_they_are() {
+    // 0x200: LD VA, 0x41; 0x202: ADD VA, 0x21
+    let binary = b"jAz!".to_vec();
+    assert_eq!(RomFormat::detect(&binary), RomFormat::Binary);
+    let rom = Rom::from_bytes(binary.clone()).unwrap();
+    assert_eq!(rom.data(), &binary[..]);
+}
+
+#[test]
+fn files_are_loaded_by_content_not_name() {
+    let dir = std::env::temp_dir().join(format!("chip_8-octo-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    // source that doesn't assemble is reported whatever the file is called
+    for name in &["broken.8o", "broken.ch8"] {
+        let source = dir.join(name);
+        std::fs::write(&source, ": main\njump nowhere\n").unwrap();
+        match Rom::load(&source) {
+            Err(RomError::Assemble(error)) => assert_eq!(error.line, 2),
+            other => panic!("expected an assembler error, got {:?}", other.err()),
+        }
+    }
+
+    let binary = dir.join("binary.8o");
+    std::fs::write(&binary, [0x00, 0xE0, 0x12, 0x00]).unwrap();
+    assert_eq!(
+        Rom::load(&binary).unwrap().data(),
+        &[0x00, 0xE0, 0x12, 0x00]
+    );
+
+    let payload = br#"{"program": ": main jump main"}"#;
+    let gif = dir.join("cartridge.8o");
+    std::fs::write(&gif, cartridge(payload.len() as u32, payload)).unwrap();
+    assert_eq!(Rom::load(&gif).unwrap().data(), &[0x12, 0x02, 0x12, 0x02]);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
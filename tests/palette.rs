@@ -0,0 +1,54 @@
+use chip_8::palette::{Palette, Rgb, Theme};
+
+#[test]
+fn colours_parse_with_or_without_hash() {
+    assert_eq!("#33FF66".parse::<Rgb>(), Ok(Rgb(0x33, 0xFF, 0x66)));
+    assert_eq!("33ff66".parse::<Rgb>(), Ok(Rgb(0x33, 0xFF, 0x66)));
+    assert!("#33FF6".parse::<Rgb>().is_err());
+    assert!("#33FG66".parse::<Rgb>().is_err());
+}
+
+#[test]
+fn colours_display_as_hex() {
+    assert_eq!(Rgb(0x0A, 0xB0, 0xFF).to_string(), "#0AB0FF");
+}
+
+#[test]
+fn theme_names_round_trip() {
+    for name in Theme::NAMES {
+        let theme: Theme = name.parse().unwrap();
+        assert_eq!(theme.name(), *name);
+    }
+    assert!("sepia".parse::<Theme>().is_err());
+}
+
+#[test]
+fn next_cycles_through_all_themes() {
+    let mut theme = Theme::Classic;
+    for _ in 0..Theme::ALL.len() {
+        theme = theme.next();
+    }
+    assert_eq!(theme, Theme::Classic);
+    assert_eq!(Theme::Classic.next(), Theme::GreenPhosphor);
+}
+
+#[test]
+fn default_palette_is_white_on_black() {
+    let palette = Palette::default();
+    assert_eq!(palette.background(), Rgb(0, 0, 0));
+    assert_eq!(palette.foreground(), Rgb(0xFF, 0xFF, 0xFF));
+}
+
+#[test]
+fn with_colors_replaces_valid_colours() {
+    let colors = vec![
+        "#112233".to_string(),
+        "bogus".to_string(),
+        "#445566".to_string(),
+    ];
+    let palette = Theme::Amber.palette().with_colors(&colors);
+    assert_eq!(palette.background(), Rgb(0x11, 0x22, 0x33));
+    assert_eq!(palette.foreground(), Theme::Amber.palette().foreground());
+    assert_eq!(palette.colors[2], Rgb(0x44, 0x55, 0x66));
+    assert_eq!(palette.colors[3], Theme::Amber.palette().colors[3]);
+}
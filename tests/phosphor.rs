@@ -0,0 +1,59 @@
+use chip_8::phosphor::{Persistence, Phosphor};
+use chip_8::screen_buffer::ScreenBuffer;
+
+// runs frames with the pixel at 1, 1 lit or not and collects its intensity
+fn intensities(persistence: Persistence, frames: &[bool]) -> Vec<u8> {
+    let mut phosphor = Phosphor::new(persistence);
+    let mut screen = ScreenBuffer::new(4, 4);
+    frames
+        .iter()
+        .map(|lit| {
+            if screen.get_pixel(1, 1) != *lit {
+                screen.xor(1, 1);
+            }
+            phosphor.update(&screen);
+            phosphor.intensity(1, 1)
+        })
+        .collect()
+}
+
+#[test]
+fn off_shows_the_screen_as_is() {
+    let frames = [true, false, true, true, false];
+    let expected = vec![255, 0, 255, 255, 0];
+    assert_eq!(intensities(Persistence::Off, &frames), expected);
+}
+
+#[test]
+fn fade_decays_over_the_frames() {
+    let frames = [true, false, false, false, false, true, false];
+    let expected = vec![255, 191, 127, 63, 0, 255, 191];
+    assert_eq!(intensities(Persistence::Fade(4), &frames), expected);
+}
+
+#[test]
+fn blend_shows_the_union_of_the_last_frames() {
+    let frames = [true, false, false, false, true, false];
+    let expected = vec![255, 255, 255, 0, 255, 255];
+    assert_eq!(intensities(Persistence::Blend(3), &frames), expected);
+    assert_eq!(
+        intensities(Persistence::Blend(1), &frames),
+        intensities(Persistence::Off, &frames)
+    );
+}
+
+#[test]
+fn update_reports_changes() {
+    let mut phosphor = Phosphor::new(Persistence::Fade(2));
+    let mut screen = ScreenBuffer::new(8, 4);
+    assert!(phosphor.update(&screen)); // the first frame sets the size
+    assert!(!phosphor.update(&screen));
+
+    screen.xor(7, 3);
+    assert!(phosphor.update(&screen));
+    screen.xor(7, 3);
+    assert!(phosphor.update(&screen));
+    assert!(phosphor.update(&screen));
+    assert!(!phosphor.update(&screen));
+    assert_eq!(phosphor.intensities(), &[0; 32][..]);
+}
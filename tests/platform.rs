@@ -0,0 +1,104 @@
+use chip_8::chip8::Chip8;
+use chip_8::memory_map::RegionKind;
+use chip_8::platform::Descriptor;
+use chip_8::rom::{Rom, RomError};
+
+fn machine(name: &str, rom: &[u8]) -> Result<Chip8, RomError> {
+    let mut chip8 = Chip8::new();
+    chip8.set_platform(Descriptor::find(name).unwrap());
+    chip8.init();
+    chip8.load_rom(Rom::from_binary(rom.to_vec()))?;
+    Ok(chip8)
+}
+
+#[test]
+fn eti660_programs_start_at_0x600() {
+    // 0x600: LD V0, 0x42
+    let mut chip8 = machine("eti660", &[0x60, 0x42]).unwrap();
+    assert_eq!(chip8.snapshot().pc, 0x600);
+    chip8.run_cycles(1);
+    assert_eq!(chip8.snapshot().regs[0], 0x42);
+
+    let program = chip8.memory_map().region(RegionKind::Program).unwrap();
+    assert_eq!(program.start, 0x600);
+}
+
+#[test]
+fn rom_size_depends_on_the_load_address() {
+    let rom = vec![0; 0xC00];
+    assert!(machine("chip8", &rom).is_ok());
+    assert!(matches!(
+        machine("eti660", &rom),
+        Err(RomError::TooLarge { max: 0xA00, .. })
+    ));
+}
+
+#[test]
+fn vip_programs_end_at_the_reserved_area() {
+    // 0xEA0 - 0x200 bytes fit, one more would overwrite the VIP stack
+    assert_eq!(Descriptor::find("vip").unwrap().max_rom_size(), 3232);
+    assert!(machine("vip", &vec![0; 3232]).is_ok());
+    assert!(matches!(
+        machine("vip", &vec![0; 3233]),
+        Err(RomError::TooLarge {
+            size: 3233,
+            max: 3232,
+            ..
+        })
+    ));
+    assert!(machine("chip8", &vec![0; 3233]).is_ok());
+}
+
+#[test]
+fn eti660_display_modes() {
+    for (name, height) in [("eti660", 32), ("eti660-64x48", 48), ("eti660-64x64", 64)] {
+        // DRW V0, V0, 1 at row 0x30 wraps unless the screen is taller
+        let rom = [0x60, 0x30, 0xA6, 0x08, 0xD0, 0x01, 0x16, 0x06, 0x80];
+        let mut chip8 = machine(name, &rom).unwrap();
+        chip8.run_cycles(3);
+        let screen = chip8.screen_buffer();
+        assert_eq!((screen.width(), screen.height()), (64, height), "{}", name);
+        let y = 0x30 % height;
+        assert!(screen.get_pixel(0x30, y), "{}", name);
+        assert_eq!(chip8.snapshot().screen.len(), 64 * height as usize);
+    }
+}
+
+#[test]
+fn fonts_and_quirks_follow_the_platform() {
+    let eti660 = Descriptor::find("eti660").unwrap();
+    assert_eq!(eti660.quirks().font, eti660.font);
+    assert_eq!(
+        Descriptor::for_database("originalChip8").unwrap().name,
+        "vip"
+    );
+    assert_eq!(Descriptor::for_database("superchip").unwrap().name, "schip");
+    assert!(Descriptor::for_database("xochip").is_none());
+    for name in Descriptor::NAMES {
+        assert!(Descriptor::find(name).is_some(), "{}", name);
+    }
+    // XO-CHIP needs 64 KiB of memory, which the core doesn't have
+    assert!(Descriptor::find("xochip").is_none());
+}
+
+#[test]
+fn the_reserved_region_comes_from_the_descriptor() {
+    let vip = machine("vip", &[]).unwrap();
+    let reserved = vip.memory_map().region(RegionKind::Reserved).unwrap();
+    assert_eq!((reserved.start, reserved.end), (0xEA0, 0x1000));
+    assert!(machine("chip8", &[])
+        .unwrap()
+        .memory_map()
+        .region(RegionKind::Reserved)
+        .is_none());
+
+    let custom = Descriptor {
+        name: "custom",
+        reserved: Some(0xF00),
+        ..Descriptor::default()
+    };
+    let mut chip8 = Chip8::new();
+    chip8.set_platform(&custom);
+    let program = chip8.memory_map().region(RegionKind::Program).unwrap();
+    assert_eq!((program.start, program.end), (0x200, 0xF00));
+}
@@ -0,0 +1,88 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use chip_8::chip8::Chip8;
+use chip_8::fault::Fault;
+use chip_8::policy::OpcodePolicy;
+use chip_8::rom::Rom;
+
+const SYS: u16 = 0x0123;
+const UNKNOWN: u16 = 0xE1FF;
+const LOAD_V1: u16 = 0x6142; // runs after the opcode under test
+
+fn machine(opcode: u16, policy: OpcodePolicy) -> Chip8 {
+    let mut chip8 = Chip8::new();
+    chip8.set_sys_policy(policy);
+    chip8.set_unknown_policy(policy);
+    #[cfg(feature = "dynarec")]
+    chip8.set_dynarec(true);
+    chip8.init();
+    let rom = [opcode, LOAD_V1]
+        .iter()
+        .flat_map(|op| op.to_be_bytes().to_vec())
+        .collect();
+    chip8.load_rom(Rom::from_binary(rom)).unwrap();
+    chip8
+}
+
+#[test]
+fn ignore_and_warn_skip_the_opcode() {
+    for opcode in [SYS, UNKNOWN] {
+        for (policy, warnings) in [(OpcodePolicy::Ignore, 0), (OpcodePolicy::Warn, 1)] {
+            let mut chip8 = machine(opcode, policy);
+            chip8.run_cycles(2);
+            assert_eq!(chip8.fault(), None);
+            assert_eq!(chip8.snapshot().regs[1], 0x42);
+            assert_eq!(chip8.warnings(), warnings, "{:?}", policy);
+        }
+    }
+}
+
+#[test]
+fn halt_stops_on_the_opcode() {
+    let mut chip8 = machine(SYS, OpcodePolicy::Halt);
+    chip8.run_cycles(2);
+    let fault = Fault::MachineCode {
+        addr: 0x200,
+        target: 0x123,
+    };
+    assert_eq!(chip8.fault(), Some(fault));
+    assert_eq!(chip8.snapshot().pc, 0x200);
+
+    let mut chip8 = machine(UNKNOWN, OpcodePolicy::Halt);
+    chip8.run_cycles(2);
+    let fault = Fault::InvalidOpcode {
+        addr: 0x200,
+        raw: UNKNOWN,
+    };
+    assert_eq!(chip8.fault(), Some(fault));
+    assert_eq!(chip8.snapshot().regs[1], 0);
+}
+
+#[test]
+fn trap_calls_the_handler() {
+    let trapped = Rc::new(RefCell::new(Vec::new()));
+    let mut chip8 = machine(UNKNOWN, OpcodePolicy::Trap);
+    let log = trapped.clone();
+    chip8.set_trap(move |_, raw| {
+        log.borrow_mut().push(raw);
+        true
+    });
+    chip8.run_cycles(2);
+    assert_eq!(*trapped.borrow(), vec![UNKNOWN]);
+    assert_eq!(chip8.fault(), None);
+    assert_eq!(chip8.snapshot().regs[1], 0x42);
+}
+
+#[test]
+fn trap_halts_when_the_handler_refuses() {
+    let mut chip8 = machine(SYS, OpcodePolicy::Trap);
+    chip8.set_trap(|_, _| false);
+    chip8.run_cycles(2);
+    assert!(matches!(chip8.fault(), Some(Fault::MachineCode { .. })));
+
+    // as it does without a handler
+    let mut chip8 = machine(SYS, OpcodePolicy::Trap);
+    chip8.run_cycles(2);
+    assert!(matches!(chip8.fault(), Some(Fault::MachineCode { .. })));
+}
@@ -0,0 +1,103 @@
+use gif::SetParameter;
+
+use chip_8::palette::{Palette, Rgb};
+use chip_8::recorder::{Recorder, VideoFormat};
+use chip_8::screen_buffer::ScreenBuffer;
+
+fn palette() -> Palette {
+    Palette {
+        colors: [
+            Rgb(0, 0, 0),
+            Rgb(0xFF, 0xFF, 0xFF),
+            Rgb(0xFF, 0, 0),
+            Rgb(0, 0, 0xFF),
+        ],
+    }
+}
+
+// records screens of 2x1 pixels, the left one lit as given for each frame
+fn record(format: VideoFormat, frames: &[bool], scale: u16) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut recorder = Recorder::new(&mut out, format, 2, 1, palette(), scale).unwrap();
+    let mut screen = ScreenBuffer::new(2, 1);
+    for lit in frames {
+        if screen.get_pixel(0, 0) != *lit {
+            screen.xor(0, 0);
+        }
+        recorder.frame(&screen).unwrap();
+    }
+    assert_eq!(recorder.frames(), frames.len() as u32);
+    recorder.finish().unwrap();
+    out
+}
+
+// the delay and pixel indices of every frame
+fn decode_gif(data: &[u8]) -> Vec<(u16, Vec<u8>)> {
+    let mut decoder = gif::Decoder::new(data);
+    decoder.set(gif::ColorOutput::Indexed);
+    let mut reader = decoder.read_info().unwrap();
+    let mut frames = Vec::new();
+    while let Some(frame) = reader.read_next_frame().unwrap() {
+        frames.push((frame.delay, frame.buffer.to_vec()));
+    }
+    frames
+}
+
+#[test]
+fn gif_merges_unchanged_frames() {
+    let frames = [true, true, true, false, true];
+    let gif = record(VideoFormat::Gif, &frames, 1);
+    let expected = vec![(5, vec![1, 0]), (2, vec![0, 0]), (1, vec![1, 0])];
+    assert_eq!(decode_gif(&gif), expected);
+}
+
+#[test]
+fn gif_delays_add_up_to_60_hz() {
+    let frames: Vec<bool> = (0..60).map(|frame| frame % 2 == 0).collect();
+    let gif = record(VideoFormat::Gif, &frames, 1);
+    let frames = decode_gif(&gif);
+    assert_eq!(frames.len(), 60);
+    let total: u16 = frames.iter().map(|(delay, _)| delay).sum();
+    assert_eq!(total, 100);
+}
+
+#[test]
+fn gif_frames_are_scaled() {
+    let gif = record(VideoFormat::Gif, &[true], 2);
+    assert_eq!(decode_gif(&gif), vec![(2, vec![1, 1, 0, 0, 1, 1, 0, 0])]);
+}
+
+#[test]
+fn y4m_has_a_header_and_three_planes_per_frame() {
+    let y4m = record(VideoFormat::Y4m, &[true, false], 1);
+    let header = b"YUV4MPEG2 W2 H1 F60:1 Ip A1:1 C444\n";
+    assert_eq!(&y4m[..header.len()], &header[..]);
+
+    let frames = &y4m[header.len()..];
+    #[rustfmt::skip]
+    let expected = [
+        b"FRAME\n".as_ref(), &[235, 16], &[128, 128], &[128, 128],
+        b"FRAME\n".as_ref(), &[16, 16], &[128, 128], &[128, 128],
+    ]
+    .concat();
+    assert_eq!(frames, &expected[..]);
+}
+
+#[test]
+fn a_changed_screen_size_is_an_error() {
+    let mut out = Vec::new();
+    let mut recorder = Recorder::new(&mut out, VideoFormat::Y4m, 2, 1, palette(), 1).unwrap();
+    assert!(recorder.frame(&ScreenBuffer::new(4, 2)).is_err());
+}
+
+#[test]
+fn formats_are_found_by_extension() {
+    assert_eq!(VideoFormat::for_path("demo.gif"), Some(VideoFormat::Gif));
+    assert_eq!(
+        VideoFormat::for_path("out/demo.Y4M"),
+        Some(VideoFormat::Y4m)
+    );
+    assert_eq!(VideoFormat::for_path("-"), Some(VideoFormat::Y4m));
+    assert_eq!(VideoFormat::for_path("demo.mp4"), None);
+    assert_eq!(VideoFormat::for_path("demo"), None);
+}
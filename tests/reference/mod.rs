@@ -0,0 +1,250 @@
+// A deliberately simple CHIP-8 written straight from the instruction table,
+// without any of the caching or decoding layers of the real core. It stops
+// on anything the core does not support instead of guessing, including a
+// stack that wraps around.
+
+use chip_8::chip8::{Snapshot, REGISTERS, SCREEN_HEIGHT, SCREEN_WIDTH};
+use chip_8::font::Font;
+use chip_8::quirks::{IndexIncrement, Quirks};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+const WIDTH: usize = SCREEN_WIDTH as usize;
+const HEIGHT: usize = SCREEN_HEIGHT as usize;
+
+pub struct Reference {
+    memory: [u8; 4096],
+    v: [u8; REGISTERS],
+    i: u16,
+    pc: u16,
+    stack: Vec<u16>,
+    delay_timer: u8,
+    sound_timer: u8,
+    keys: [bool; 16],
+    screen: [[bool; WIDTH]; HEIGHT],
+    quirks: Quirks,
+    rng: StdRng,
+}
+
+impl Reference {
+    pub fn new(rom: &[u8], quirks: Quirks, seed: u64) -> Self {
+        let mut memory = [0; 4096];
+        // glyphs are data rather than behaviour, so they come from the core
+        let font = Font::builtin(quirks.font);
+        memory[0x50..0x50 + font.small().len()].copy_from_slice(font.small());
+        if let Some(big) = font.big() {
+            memory[0xA0..0xA0 + big.len()].copy_from_slice(big);
+        }
+        memory[0x200..0x200 + rom.len()].copy_from_slice(rom);
+        Reference {
+            memory,
+            v: [0; REGISTERS],
+            i: 0,
+            pc: 0x200,
+            stack: Vec::new(),
+            delay_timer: 0,
+            sound_timer: 0,
+            keys: [false; 16],
+            screen: [[false; WIDTH]; HEIGHT],
+            quirks,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn set_key(&mut self, key: u8, pressed: bool) {
+        self.keys[key as usize] = pressed;
+    }
+
+    pub fn tick_timers(&mut self) {
+        self.delay_timer = self.delay_timer.saturating_sub(1);
+        self.sound_timer = self.sound_timer.saturating_sub(1);
+    }
+
+    pub fn pc(&self) -> u16 {
+        self.pc
+    }
+
+    // the instruction step() is about to execute, if there is one
+    pub fn next_opcode(&self) -> Option<u16> {
+        if self.pc > 0xFFE {
+            return None;
+        }
+        let pc = self.pc as usize;
+        Some((self.memory[pc] as u16) << 8 | self.memory[pc + 1] as u16)
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            regs: self.v,
+            i_reg: self.i,
+            pc: self.pc,
+            stack: self.stack.clone(),
+            delay_timer: self.delay_timer,
+            sound_timer: self.sound_timer,
+            screen: self
+                .screen
+                .iter()
+                .flat_map(|row| row.iter().copied())
+                .collect(),
+        }
+    }
+
+    // Err when the program did something the core has no defined behaviour for
+    pub fn step(&mut self) -> Result<(), String> {
+        let opcode = self.next_opcode().ok_or("PC left memory")?;
+        let x = ((opcode >> 8) & 0xF) as usize;
+        let y = ((opcode >> 4) & 0xF) as usize;
+        let n = (opcode & 0xF) as u8;
+        let nn = (opcode & 0xFF) as u8;
+        let nnn = opcode & 0xFFF;
+        let invalid = || Err(format!("unsupported opcode 0x{:04X}", opcode));
+        self.pc += 2;
+
+        match opcode >> 12 {
+            0x0 if opcode == 0x00E0 => self.screen = [[false; WIDTH]; HEIGHT],
+            0x0 if opcode == 0x00EE => {
+                self.pc = self.stack.pop().ok_or("stack underflow")?;
+            }
+            0x0 => (), // machine code routines are ignored
+            0x1 => self.pc = nnn,
+            0x2 => {
+                if Some(self.stack.len()) == self.quirks.stack_depth {
+                    return Err("stack overflow".to_string());
+                }
+                self.stack.push(self.pc);
+                self.pc = nnn;
+            }
+            0x3 => self.skip_if(self.v[x] == nn),
+            0x4 => self.skip_if(self.v[x] != nn),
+            0x5 if n == 0 => self.skip_if(self.v[x] == self.v[y]),
+            0x6 => self.v[x] = nn,
+            0x7 => self.v[x] = self.v[x].wrapping_add(nn),
+            0x8 => {
+                let (vx, vy) = (self.v[x], self.v[y]);
+                let source = if self.quirks.shift_uses_vy { vy } else { vx };
+                // the flag is written after the result
+                match n {
+                    0x0 => self.v[x] = vy,
+                    0x1 => self.logic(x, vx | vy),
+                    0x2 => self.logic(x, vx & vy),
+                    0x3 => self.logic(x, vx ^ vy),
+                    0x4 => {
+                        self.v[x] = vx.wrapping_add(vy);
+                        self.v[0xF] = (vx as u16 + vy as u16 > 0xFF) as u8;
+                    }
+                    0x5 => {
+                        self.v[x] = vx.wrapping_sub(vy);
+                        self.v[0xF] = (vx >= vy) as u8;
+                    }
+                    0x6 => {
+                        self.v[x] = source >> 1;
+                        self.v[0xF] = source & 1;
+                    }
+                    0x7 => {
+                        self.v[x] = vy.wrapping_sub(vx);
+                        self.v[0xF] = (vy >= vx) as u8;
+                    }
+                    0xE => {
+                        self.v[x] = source << 1;
+                        self.v[0xF] = source >> 7;
+                    }
+                    _ => return invalid(),
+                }
+            }
+            0x9 if n == 0 => self.skip_if(self.v[x] != self.v[y]),
+            0xA => self.i = nnn,
+            0xB => {
+                let offset = if self.quirks.jump_uses_vx {
+                    self.v[x]
+                } else {
+                    self.v[0]
+                };
+                self.pc = nnn + offset as u16;
+            }
+            0xC => self.v[x] = self.rng.gen::<u8>() & nn,
+            0xD => self.draw(self.v[x] as usize, self.v[y] as usize, n)?,
+            0xE if nn == 0x9E => self.skip_if(self.keys[(self.v[x] & 0xF) as usize]),
+            0xE if nn == 0xA1 => self.skip_if(!self.keys[(self.v[x] & 0xF) as usize]),
+            0xF => match nn {
+                0x07 => self.v[x] = self.delay_timer,
+                0x0A => match self.keys.iter().position(|pressed| *pressed) {
+                    Some(key) => self.v[x] = key as u8,
+                    None => self.pc -= 2, // wait by running this again
+                },
+                0x15 => self.delay_timer = self.v[x],
+                0x18 => self.sound_timer = self.v[x],
+                0x1E => self.i = (self.i + self.v[x] as u16) & 0xFFF,
+                0x29 => self.i = 0x50 + (self.v[x] & 0xF) as u16 * 5,
+                0x33 => {
+                    let i = self.checked_index(3)?;
+                    self.memory[i] = self.v[x] / 100;
+                    self.memory[i + 1] = self.v[x] / 10 % 10;
+                    self.memory[i + 2] = self.v[x] % 10;
+                }
+                0x55 | 0x65 => {
+                    let i = self.checked_index(x + 1)?;
+                    for r in 0..=x {
+                        if nn == 0x55 {
+                            self.memory[i + r] = self.v[r];
+                        } else {
+                            self.v[r] = self.memory[i + r];
+                        }
+                    }
+                    self.i = match self.quirks.load_store_index {
+                        IndexIncrement::Unchanged => self.i,
+                        IndexIncrement::ByX => (self.i + x as u16) & 0xFFF,
+                        IndexIncrement::ByXPlusOne => (self.i + x as u16 + 1) & 0xFFF,
+                    };
+                }
+                _ => return invalid(),
+            },
+            _ => return invalid(),
+        }
+        Ok(())
+    }
+
+    fn skip_if(&mut self, condition: bool) {
+        if condition {
+            self.pc += 2;
+        }
+    }
+
+    fn logic(&mut self, x: usize, result: u8) {
+        self.v[x] = result;
+        if self.quirks.logic_resets_vf {
+            self.v[0xF] = 0;
+        }
+    }
+
+    fn checked_index(&self, len: usize) -> Result<usize, String> {
+        let i = self.i as usize;
+        if i + len > self.memory.len() {
+            return Err(format!("access of {} bytes at 0x{:03X}", len, i));
+        }
+        Ok(i)
+    }
+
+    fn draw(&mut self, x: usize, y: usize, n: u8) -> Result<(), String> {
+        let i = self.checked_index(n as usize)?;
+        let (x, y) = (x % WIDTH, y % HEIGHT);
+        self.v[0xF] = 0;
+        for row in 0..n as usize {
+            let bits = self.memory[i + row];
+            for col in 0..8 {
+                if bits & (0x80 >> col) == 0 {
+                    continue;
+                }
+                let (px, py) = (x + col, y + row);
+                if self.quirks.clip_sprites && (px >= WIDTH || py >= HEIGHT) {
+                    continue;
+                }
+                let pixel = &mut self.screen[py % HEIGHT][px % WIDTH];
+                if *pixel {
+                    self.v[0xF] = 1;
+                }
+                *pixel = !*pixel;
+            }
+        }
+        Ok(())
+    }
+}
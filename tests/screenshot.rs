@@ -0,0 +1,99 @@
+use chip_8::chip8::Chip8;
+use chip_8::palette::{Palette, Rgb, Theme};
+use chip_8::screen_buffer::ScreenBuffer;
+use chip_8::screenshot::{self, ImageFormat};
+
+// a 3x2 screen with the pixels at 0, 0 and 2, 1 lit
+fn screen() -> ScreenBuffer {
+    let mut screen = ScreenBuffer::new(3, 2);
+    screen.xor(0, 0);
+    screen.xor(2, 1);
+    screen
+}
+
+fn text(data: Vec<u8>) -> String {
+    String::from_utf8(data).unwrap()
+}
+
+// the size and RGB pixels of a PNG file
+fn decode_png(data: &[u8]) -> (u32, u32, Vec<u8>) {
+    let (info, mut reader) = png::Decoder::new(data).read_info().unwrap();
+    assert_eq!(
+        (info.color_type, info.bit_depth),
+        (png::ColorType::RGB, png::BitDepth::Eight)
+    );
+    let mut pixels = vec![0; info.buffer_size()];
+    reader.next_frame(&mut pixels).unwrap();
+    (info.width, info.height, pixels)
+}
+
+#[test]
+fn pbm_has_a_bit_per_pixel() {
+    let pbm = screenshot::encode(&screen(), &Palette::default(), 1, ImageFormat::Pbm);
+    assert_eq!(text(pbm), "P1\n3 2\n1 0 0\n0 0 1\n");
+}
+
+#[test]
+fn pgm_uses_the_brightness_of_the_palette() {
+    let palette = Palette {
+        colors: [Rgb(0, 0, 0), Rgb(0, 0xFF, 0), Rgb(0, 0, 0), Rgb(0, 0, 0)],
+    };
+    let pgm = screenshot::encode(&screen(), &palette, 1, ImageFormat::Pgm);
+    assert_eq!(text(pgm), "P2\n3 2\n255\n149 0 0\n0 0 149\n");
+}
+
+#[test]
+fn scale_repeats_pixels() {
+    let pbm = screenshot::encode(&screen(), &Palette::default(), 2, ImageFormat::Pbm);
+    let expected = "P1\n6 4\n1 1 0 0 0 0\n1 1 0 0 0 0\n0 0 0 0 1 1\n0 0 0 0 1 1\n";
+    assert_eq!(text(pbm), expected);
+}
+
+#[test]
+fn png_holds_the_screen_in_palette_colours() {
+    let palette = Theme::Amber.palette();
+    let png = screenshot::encode(&screen(), &palette, 1, ImageFormat::Png);
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+
+    let (width, height, pixels) = decode_png(&png);
+    assert_eq!((width, height), (3, 2));
+
+    let Rgb(fr, fg, fb) = palette.foreground();
+    let Rgb(br, bg, bb) = palette.background();
+    #[rustfmt::skip]
+    let expected = vec![
+        fr, fg, fb, br, bg, bb, br, bg, bb,
+        br, bg, bb, br, bg, bb, fr, fg, fb,
+    ];
+    assert_eq!(pixels, expected);
+}
+
+#[test]
+fn png_scales_large_screens() {
+    let screen = ScreenBuffer::new(128, 64);
+    let png = screenshot::encode(&screen, &Palette::default(), 4, ImageFormat::Png);
+    let (width, height, pixels) = decode_png(&png);
+    assert_eq!((width, height), (512, 256));
+    assert_eq!(pixels.len(), 512 * 256 * 3);
+}
+
+#[test]
+fn file_names_carry_rom_and_frame() {
+    assert_eq!(
+        screenshot::file_name("BRIX", 1234, ImageFormat::Png.extension()),
+        "BRIX-001234.png"
+    );
+    assert_eq!("PGM".parse::<ImageFormat>(), Ok(ImageFormat::Pgm));
+}
+
+#[test]
+fn frames_are_counted_from_the_reset() {
+    let mut chip8 = Chip8::new();
+    chip8.init();
+    for _ in 0..3 {
+        chip8.run_frame();
+    }
+    assert_eq!(chip8.frame(), 3);
+    chip8.reset();
+    assert_eq!(chip8.frame(), 0);
+}
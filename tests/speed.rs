@@ -0,0 +1,114 @@
+use chip_8::speed::{Pace, RateMeter, Speed, SpeedControl};
+
+fn ticks(control: &mut SpeedControl, count: usize) -> Vec<Pace> {
+    (0..count).map(|_| control.tick()).collect()
+}
+
+#[test]
+fn normal_speed_runs_a_frame_per_tick() {
+    let mut control = SpeedControl::new(Speed::Unlimited);
+    assert_eq!(ticks(&mut control, 3), vec![Pace::Frames(1); 3]);
+}
+
+#[test]
+fn fractions_skip_ticks() {
+    let mut control = SpeedControl::new(Speed::Unlimited);
+    control.set_speed(Speed::Fraction(3));
+    let expected = [0, 0, 1, 0, 0, 1].iter().map(|n| Pace::Frames(*n));
+    assert_eq!(ticks(&mut control, 6), expected.collect::<Vec<_>>());
+}
+
+#[test]
+fn turbo_applies_while_held() {
+    let mut control = SpeedControl::new(Speed::Multiple(4));
+    control.set_turbo(true);
+    assert_eq!(control.tick(), Pace::Frames(4));
+    control.set_turbo(false);
+    assert_eq!(control.tick(), Pace::Frames(1));
+
+    let mut control = SpeedControl::new(Speed::Unlimited);
+    control.set_turbo(true);
+    assert_eq!(control.tick(), Pace::Unlimited);
+}
+
+#[test]
+fn pause_stops_and_advance_runs_single_frames() {
+    let mut control = SpeedControl::new(Speed::Unlimited);
+    control.set_paused(true);
+    assert_eq!(ticks(&mut control, 2), vec![Pace::Frames(0); 2]);
+
+    control.advance();
+    control.advance();
+    let expected = vec![Pace::Frames(1), Pace::Frames(1), Pace::Frames(0)];
+    assert_eq!(ticks(&mut control, 3), expected);
+    assert!(control.paused());
+
+    // turbo does not run a paused machine either
+    control.set_turbo(true);
+    assert_eq!(control.tick(), Pace::Frames(0));
+}
+
+#[test]
+fn advance_pauses_a_running_machine() {
+    let mut control = SpeedControl::new(Speed::Unlimited);
+    control.advance();
+    assert!(control.paused());
+    assert_eq!(
+        ticks(&mut control, 2),
+        vec![Pace::Frames(1), Pace::Frames(0)]
+    );
+}
+
+#[test]
+fn faster_and_slower_step_through_the_speeds() {
+    let mut control = SpeedControl::new(Speed::Unlimited);
+    assert_eq!(control.faster(), Speed::Multiple(2));
+    assert_eq!(control.slower(), Speed::NORMAL);
+    assert_eq!(control.slower(), Speed::Fraction(2));
+    for _ in 0..10 {
+        control.slower();
+    }
+    assert_eq!(control.speed(), Speed::Fraction(8));
+
+    control.set_speed(Speed::Multiple(3));
+    assert_eq!(control.faster(), Speed::Multiple(4));
+    control.set_speed(Speed::Multiple(3));
+    assert_eq!(control.slower(), Speed::Multiple(2));
+    for _ in 0..10 {
+        control.faster();
+    }
+    assert_eq!(control.speed(), Speed::Multiple(8));
+}
+
+#[test]
+fn speeds_parse_and_display() {
+    for (text, speed) in &[
+        ("4", Speed::Multiple(4)),
+        ("1/4", Speed::Fraction(4)),
+        ("1/1", Speed::NORMAL),
+        ("unlimited", Speed::Unlimited),
+    ] {
+        assert_eq!(text.parse::<Speed>(), Ok(*speed));
+    }
+    for text in &["0", "65", "2/3", "1/0", "fast"] {
+        assert!(text.parse::<Speed>().is_err(), "{}", text);
+    }
+    assert_eq!(Speed::Fraction(4).to_string(), "x1/4");
+    assert_eq!(Speed::Multiple(2).to_string(), "x2");
+}
+
+#[test]
+fn rates_are_averaged_over_the_last_second() {
+    let mut meter = RateMeter::new();
+    assert_eq!((meter.fps(), meter.ips()), (0, 0));
+    for _ in 0..30 {
+        meter.record(1, 10);
+    }
+    assert_eq!((meter.fps(), meter.ips()), (60, 600));
+
+    // a second of turbo pushes the slow frames out
+    for _ in 0..60 {
+        meter.record(4, 40);
+    }
+    assert_eq!((meter.fps(), meter.ips()), (240, 2400));
+}
@@ -0,0 +1,84 @@
+use chip_8::chip8::Chip8;
+use chip_8::fault::Fault;
+use chip_8::quirks::{QuirkPreset, Quirks};
+use chip_8::rom::Rom;
+use chip_8::stack::Frame;
+
+const CALL_SELF: [u8; 2] = [0x22, 0x00]; // 0x200: CALL 0x200
+
+fn machine(quirks: Quirks, rom: &[u8]) -> Chip8 {
+    let mut chip8 = Chip8::new();
+    chip8.set_quirks(quirks);
+    #[cfg(feature = "dynarec")]
+    chip8.set_dynarec(true);
+    chip8.init();
+    chip8.load_rom(Rom::from_binary(rom.to_vec())).unwrap();
+    chip8
+}
+
+#[test]
+fn vip_stack_wraps_after_twelve_calls() {
+    let mut chip8 = machine(Quirks::from_preset(QuirkPreset::Vip), &CALL_SELF);
+    chip8.run_cycles(12);
+    assert_eq!(chip8.call_stack().len(), 12);
+    chip8.run_cycles(1);
+    assert_eq!(chip8.fault(), None);
+    assert_eq!(chip8.call_stack().len(), 1);
+}
+
+#[test]
+fn schip_stack_halts_after_sixteen_calls() {
+    let mut chip8 = machine(Quirks::from_preset(QuirkPreset::Schip), &CALL_SELF);
+    chip8.run_cycles(17);
+    assert_eq!(chip8.fault(), Some(Fault::StackOverflow { addr: 0x200 }));
+    assert_eq!(chip8.call_stack().len(), 16);
+}
+
+#[test]
+fn unlimited_stack_goes_deeper() {
+    let quirks = Quirks {
+        stack_depth: None,
+        ..Quirks::default()
+    };
+    let mut chip8 = machine(quirks, &CALL_SELF);
+    chip8.run_cycles(1000);
+    assert_eq!(chip8.fault(), None);
+    assert_eq!(chip8.call_stack().len(), 1000);
+}
+
+#[test]
+fn underflow_halts_unless_the_stack_wraps() {
+    let ret = [0x00, 0xEE];
+    let mut chip8 = machine(Quirks::from_preset(QuirkPreset::Schip), &ret);
+    chip8.run_cycles(1);
+    assert_eq!(chip8.fault(), Some(Fault::StackUnderflow { addr: 0x200 }));
+
+    // the VIP returns to whatever the top entry holds, zero on a fresh machine
+    let mut chip8 = machine(Quirks::from_preset(QuirkPreset::Vip), &ret);
+    chip8.run_cycles(1);
+    assert_eq!(chip8.fault(), None);
+    assert_eq!(chip8.snapshot().pc, 0);
+}
+
+#[test]
+fn frames_record_the_call_site() {
+    // 0x200: CALL 0x206, 0x202: JP 0x202, 0x204: RET, 0x206: CALL 0x204
+    let rom = [0x22, 0x06, 0x12, 0x02, 0x00, 0xEE, 0x22, 0x04];
+    let mut chip8 = machine(Quirks::default(), &rom);
+    chip8.run_cycles(2);
+    let frames = [
+        Frame {
+            call_site: 0x200,
+            return_addr: 0x202,
+        },
+        Frame {
+            call_site: 0x206,
+            return_addr: 0x208,
+        },
+    ];
+    assert_eq!(chip8.call_stack(), frames);
+    assert_eq!(chip8.snapshot().stack, vec![0x202, 0x208]);
+
+    chip8.run_cycles(1);
+    assert_eq!(chip8.call_stack(), &frames[..1]);
+}
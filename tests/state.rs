@@ -0,0 +1,100 @@
+use chip_8::chip8::Chip8;
+use chip_8::quirks::{QuirkPreset, Quirks};
+use chip_8::rom::Rom;
+use chip_8::state::{StateError, VERSION};
+
+const SEED: u64 = 8;
+
+fn machine(quirks: Quirks, rom: Vec<u8>) -> Chip8 {
+    let mut chip8 = Chip8::new();
+    chip8.set_quirks(quirks);
+    chip8.seed(SEED);
+    chip8.init();
+    chip8.load_rom(Rom::from_binary(rom)).unwrap();
+    chip8
+}
+
+fn brix() -> Vec<u8> {
+    Rom::load("roms/BRIX").unwrap().into()
+}
+
+#[test]
+fn a_loaded_state_matches_the_saved_machine() {
+    let quirks = Quirks::default();
+    let mut chip8 = machine(quirks, brix());
+    for _ in 0..120 {
+        chip8.run_frame();
+    }
+    let state = chip8.save_state();
+
+    let mut restored = Chip8::new();
+    restored.set_quirks(quirks);
+    restored.load_state(&state).unwrap();
+    assert_eq!(restored.snapshot(), chip8.snapshot());
+    assert_eq!(restored.save_state(), state);
+}
+
+#[test]
+fn an_unlimited_stack_is_saved_at_any_depth() {
+    let quirks = Quirks {
+        stack_depth: None,
+        ..Quirks::from_preset(QuirkPreset::Chip8)
+    };
+    // 0x200: CALL 0x200
+    let mut chip8 = machine(quirks, vec![0x22, 0x00]);
+    chip8.run_cycles(70_000);
+    assert_eq!(chip8.call_stack().len(), 70_000);
+
+    let mut restored = Chip8::new();
+    restored.set_quirks(quirks);
+    restored.load_state(&chip8.save_state()).unwrap();
+    assert_eq!(restored.call_stack().len(), 70_000);
+    assert_eq!(restored.snapshot(), chip8.snapshot());
+}
+
+#[test]
+fn a_stack_deeper_than_the_quirks_allow_is_rejected() {
+    let unlimited = Quirks {
+        stack_depth: None,
+        ..Quirks::from_preset(QuirkPreset::Chip8)
+    };
+    let mut chip8 = machine(unlimited, vec![0x22, 0x00]);
+    chip8.run_cycles(20);
+
+    let mut restored = Chip8::new();
+    restored.set_quirks(Quirks::from_preset(QuirkPreset::Schip));
+    assert_eq!(
+        restored.load_state(&chip8.save_state()),
+        Err(StateError::Invalid("stack depth"))
+    );
+}
+
+#[test]
+fn broken_states_are_rejected_and_leave_the_machine_alone() {
+    let mut chip8 = machine(Quirks::default(), brix());
+    for _ in 0..10 {
+        chip8.run_frame();
+    }
+    let state = chip8.save_state();
+    let mut other = machine(Quirks::default(), vec![0x12, 0x00]);
+    let before = other.snapshot();
+
+    assert_eq!(
+        other.load_state(&state[..state.len() - 1]),
+        Err(StateError::Truncated)
+    );
+    assert_eq!(other.load_state(&state[..3]), Err(StateError::BadMagic));
+
+    let mut old = state.clone();
+    old[4] = VERSION - 1;
+    assert_eq!(
+        other.load_state(&old),
+        Err(StateError::UnsupportedVersion(VERSION - 1))
+    );
+
+    let mut longer = state.clone();
+    longer.push(0);
+    assert_eq!(other.load_state(&longer), Err(StateError::TrailingData(1)));
+
+    assert_eq!(other.snapshot(), before);
+}
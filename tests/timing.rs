@@ -0,0 +1,167 @@
+use chip_8::chip8::Chip8;
+use chip_8::opcode::OpCode;
+use chip_8::quirks::{QuirkPreset, Quirks};
+use chip_8::rom::Rom;
+use chip_8::timing::{self, Timing, VIP_FETCH_CYCLES};
+
+fn machine(timing: Timing, rom: Vec<u8>) -> Chip8 {
+    let mut chip8 = Chip8::new();
+    chip8.set_timing(timing);
+    chip8.init();
+    chip8.load_rom(Rom::from_binary(rom)).unwrap();
+    chip8
+}
+
+#[test]
+fn a_second_runs_the_cycles_of_the_vip_clock() {
+    // JP 0x200 takes 40 machine cycles of fetching and 12 of its own, and a
+    // frame leaves 3668 - 1072 = 2596 of them to the interpreter
+    let mut chip8 = machine(Timing::Vip, vec![0x12, 0x00]);
+    let frames: Vec<u32> = (0..60).map(|_| chip8.run_frame()).collect();
+    // 50 jumps overrun a frame by 4 cycles until a frame of 49 catches up
+    assert_eq!(
+        frames[..13],
+        [50, 50, 50, 50, 50, 50, 50, 50, 50, 50, 50, 50, 49]
+    );
+    // 2996 * 52 cycles, the 1_760_640 Hz clock at 8 clocks a cycle less 60
+    // frames of display DMA and timer interrupt is 155_760
+    assert_eq!(frames.iter().sum::<u32>(), 2996);
+}
+
+#[test]
+fn skips_cost_more_when_taken() {
+    let regs = [0; 16];
+    let ske = OpCode::SKE { s: 0, nn: 0 };
+    assert_eq!(timing::vip_cycles(ske, &regs, false), 50);
+    assert_eq!(timing::vip_cycles(ske, &regs, true), 54);
+    let skp = OpCode::SKP { s: 0 };
+    assert_eq!(timing::vip_cycles(skp, &regs, false), 54);
+    assert_eq!(timing::vip_cycles(skp, &regs, true), 58);
+    // only skips care
+    let load = OpCode::LOAD { s: 0, nn: 0 };
+    assert_eq!(timing::vip_cycles(load, &regs, true), 46);
+    assert_eq!(timing::vip_cycles(load, &regs, false), 46);
+
+    // the same loop of a skip and JP 0x200 runs slower when it skips
+    let mut chip8 = machine(Timing::Vip, vec![0x30, 0x00, 0x00, 0x00, 0x12, 0x00]);
+    let skipping = chip8.run_frame();
+    let mut chip8 = machine(Timing::Vip, vec![0x30, 0x01, 0x12, 0x00]);
+    assert!(chip8.run_frame() > skipping);
+}
+
+#[test]
+fn skips_past_the_end_of_memory_are_taken_too() {
+    // writes JP 0xFFE to 0x002, where SE V0, 0 at 0xFFE skips to
+    let mut rom = vec![0; 0xE00];
+    #[rustfmt::skip]
+    rom[..12].copy_from_slice(&[
+        0xA0, 0x02, // 0x200: LD I, 0x002
+        0x60, 0x1F, // 0x202: LD V0, 0x1F
+        0x61, 0xFE, // 0x204: LD V1, 0xFE
+        0xF1, 0x55, // 0x206: LD [I], V1
+        0x60, 0x00, // 0x208: LD V0, 0
+        0x1F, 0xFE, // 0x20A: JP 0xFFE
+    ]);
+    rom[0xDFE..].copy_from_slice(&[0x30, 0x00]); // 0xFFE: SE V0, 0
+    let mut chip8 = machine(Timing::Vip, rom);
+    let frames: Vec<u32> = (0..60).map(|_| chip8.run_frame()).collect();
+    // 54 and 52 cycles a round, 51 instructions a frame if the skip were free
+    assert_eq!(frames[..3], [49, 49, 49]);
+    assert_eq!(frames.iter().sum::<u32>(), 2939);
+    assert_eq!(chip8.fault(), None);
+}
+
+#[test]
+fn every_instruction_pays_for_fetching() {
+    let regs = [0; 16];
+    assert_eq!(
+        timing::vip_cycles(OpCode::Unknown(0xFFFF), &regs, false),
+        VIP_FETCH_CYCLES
+    );
+    assert!(timing::vip_cycles(OpCode::LOAD { s: 0, nn: 1 }, &regs, false) > VIP_FETCH_CYCLES);
+}
+
+#[test]
+fn draw_depends_on_rows_and_alignment() {
+    let mut regs = [0; 16];
+    let draw = |n, regs: &[u8; 16]| timing::vip_cycles(OpCode::DRAW { s: 1, t: 2, n }, regs, false);
+    let row = draw(2, &regs) - draw(1, &regs);
+    assert_eq!(draw(5, &regs) - draw(0, &regs), 5 * row);
+
+    // three bits off a byte boundary, every row is shifted into two bytes
+    regs[1] = 11;
+    assert!(draw(5, &regs) - draw(0, &regs) > 10 * row);
+    // only the x register matters
+    let unaligned = draw(5, &regs);
+    regs[2] = 11;
+    assert_eq!(draw(5, &regs), unaligned);
+}
+
+#[test]
+fn bcd_and_register_transfers_depend_on_their_operands() {
+    let mut regs = [0; 16];
+    let bcd = |regs: &[u8; 16]| timing::vip_cycles(OpCode::BCD { s: 3 }, regs, false);
+    let zero = bcd(&regs);
+    regs[3] = 100;
+    let hundred = bcd(&regs);
+    regs[3] = 255; // 2 + 5 + 5 subtractions
+    assert_eq!(bcd(&regs) - zero, 12 * (hundred - zero));
+
+    let stor = |s| timing::vip_cycles(OpCode::STOR { s }, &regs, false);
+    let read = |s| timing::vip_cycles(OpCode::READ { s }, &regs, false);
+    assert_eq!(stor(15) - stor(14), stor(1) - stor(0));
+    assert_eq!(read(7), stor(7));
+}
+
+#[test]
+fn fixed_timing_runs_a_number_of_instructions() {
+    // LD DT, V0 with V0 = 10, then spin
+    let mut chip8 = machine(Timing::Fixed(7), vec![0x60, 0x0A, 0xF0, 0x15, 0x12, 0x04]);
+    assert_eq!(chip8.run_frame(), 7);
+    assert_eq!(chip8.snapshot().delay_timer, 9);
+}
+
+#[test]
+fn vip_timing_carries_overruns_into_the_next_frame() {
+    // nothing but LD V0, 0 at 46 cycles each, with 2596 cycles per frame
+    let mut chip8 = machine(Timing::Vip, [0x60, 0x00].repeat(0x400));
+    assert_eq!(chip8.run_frame(), 57);
+    // the last one ran 26 cycles into the next frame
+    assert_eq!(chip8.run_frame(), 56);
+    assert_eq!(chip8.snapshot().pc, 0x200 + 2 * 113);
+}
+
+// 0x200: DRW V0, V0, 1; 0x202: JP 0x200
+const DRAW_LOOP: [u8; 4] = [0xD0, 0x01, 0x12, 0x00];
+
+fn drawing(preset: QuirkPreset, timing: Timing) -> Chip8 {
+    let mut chip8 = machine(timing, DRAW_LOOP.to_vec());
+    chip8.set_quirks(Quirks::from_preset(preset));
+    chip8
+}
+
+#[test]
+fn display_wait_ends_the_frame_after_a_draw() {
+    let mut chip8 = drawing(QuirkPreset::Vip, Timing::Fixed(100));
+    assert_eq!(chip8.run_frame(), 1);
+    assert_eq!(chip8.draws(), 1);
+    assert_eq!(chip8.run_frame(), 2);
+    assert_eq!(chip8.draws(), 1);
+
+    let mut chip8 = drawing(QuirkPreset::Vip, Timing::Vip);
+    assert_eq!(chip8.run_frame(), 1);
+    assert_eq!(chip8.run_frame(), 2);
+    assert_eq!(chip8.draws(), 1);
+}
+
+#[test]
+fn without_display_wait_frames_draw_freely() {
+    let mut chip8 = drawing(QuirkPreset::Chip8, Timing::Fixed(100));
+    assert_eq!(chip8.run_frame(), 100);
+    assert_eq!(chip8.draws(), 50);
+
+    // running instructions outside of frames never waits
+    let mut chip8 = drawing(QuirkPreset::Vip, Timing::Fixed(100));
+    chip8.run_cycles(100);
+    assert_eq!(chip8.snapshot().pc, 0x200);
+}
@@ -0,0 +1,50 @@
+use chip_8::viewport::Viewport;
+
+#[test]
+fn exact_multiples_fill_the_window() {
+    let viewport = Viewport::fit(640, 320, 64, 32);
+    assert_eq!(
+        viewport,
+        Viewport {
+            x: 0,
+            y: 0,
+            width: 640,
+            height: 320,
+            scale: 10
+        }
+    );
+}
+
+#[test]
+fn scale_is_a_whole_number() {
+    let viewport = Viewport::fit(700, 350, 64, 32);
+    assert_eq!(viewport.scale, 10);
+    assert_eq!((viewport.x, viewport.y), (30, 15));
+}
+
+#[test]
+fn other_aspect_ratios_are_centred() {
+    let viewport = Viewport::fit(1920, 1080, 64, 32);
+    assert_eq!(viewport.scale, 30);
+    assert_eq!((viewport.width, viewport.height), (1920, 960));
+    assert_eq!((viewport.x, viewport.y), (0, 60));
+
+    let viewport = Viewport::fit(1000, 200, 64, 48);
+    assert_eq!(viewport.scale, 4);
+    assert_eq!((viewport.x, viewport.y), (372, 4));
+}
+
+#[test]
+fn small_windows_show_the_screen_unscaled() {
+    let viewport = Viewport::fit(100, 20, 128, 64);
+    assert_eq!(viewport.scale, 1);
+    assert_eq!((viewport.x, viewport.y), (0, 0));
+}
+
+#[test]
+fn grid_lines_separate_the_pixels() {
+    let viewport = Viewport::fit(20, 10, 4, 2);
+    let expected = vec![(5, 0, 1, 10), (10, 0, 1, 10), (15, 0, 1, 10), (0, 5, 20, 1)];
+    assert_eq!(viewport.grid_lines(4), expected);
+    assert!(viewport.grid_lines(6).is_empty());
+}